@@ -0,0 +1,4 @@
+ALTER TABLE game
+    ADD COLUMN winner INT
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION;
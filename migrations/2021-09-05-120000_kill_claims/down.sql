@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS kill_claim_open_index;
+
+DROP TABLE IF EXISTS kill_claim;
+
+DROP TYPE IF EXISTS kill_claim_status_t;
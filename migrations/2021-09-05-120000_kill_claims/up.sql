@@ -0,0 +1,27 @@
+CREATE TYPE kill_claim_status_t AS ENUM (
+    'PENDING',
+    'CONFIRMED',
+    'DISPUTED',
+    'REJECTED'
+);
+
+CREATE TABLE kill_claim (
+    id              INT GENERATED ALWAYS AS IDENTITY,
+    game            INT NOT NULL
+                    REFERENCES game(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    assassin        INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    target          INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    status          kill_claim_status_t NOT NULL DEFAULT 'PENDING',
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    resolved_at     TIMESTAMPTZ,
+    PRIMARY KEY (id)
+);
+
+-- A target can have at most one open (pending or disputed) claim at a time
+CREATE UNIQUE INDEX kill_claim_open_index ON kill_claim(game, target)
+    WHERE status IN ('PENDING', 'DISPUTED');
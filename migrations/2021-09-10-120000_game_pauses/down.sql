@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS game_pause_open_index;
+
+DROP TABLE IF EXISTS game_pause;
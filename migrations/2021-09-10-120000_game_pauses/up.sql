@@ -0,0 +1,12 @@
+CREATE TABLE game_pause (
+    id              INT GENERATED ALWAYS AS IDENTITY,
+    game            INT NOT NULL
+                    REFERENCES game(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    start_time      TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    end_time        TIMESTAMPTZ,
+    PRIMARY KEY (id)
+);
+
+-- A game can have at most one ongoing pause
+CREATE UNIQUE INDEX game_pause_open_index ON game_pause(game) WHERE end_time IS NULL;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS game_settings;
+
+DROP TYPE IF EXISTS kill_confirmation_t;
@@ -0,0 +1,31 @@
+CREATE TYPE kill_confirmation_t AS ENUM (
+    'ASSASSIN',
+    'VICTIM'
+);
+
+CREATE TABLE game_settings (
+    game                INT NOT NULL
+                        REFERENCES game(id)
+                            ON UPDATE CASCADE ON DELETE NO ACTION,
+    duration_minutes    INT NOT NULL,
+    min_players         INT NOT NULL,
+    allow_late_join     BOOLEAN NOT NULL,
+    kill_confirmation   kill_confirmation_t NOT NULL,
+    score_per_kill      INT NOT NULL,
+    score_for_surviving INT NOT NULL,
+    score_for_winning   INT NOT NULL,
+    PRIMARY KEY (game)
+);
+
+-- Existing games get the rules which used to be hardcoded
+INSERT INTO game_settings (
+    game,
+    duration_minutes,
+    min_players,
+    allow_late_join,
+    kill_confirmation,
+    score_per_kill,
+    score_for_surviving,
+    score_for_winning
+)
+SELECT id, 4320, 2, FALSE, 'VICTIM', 10, 5, 20 FROM game;
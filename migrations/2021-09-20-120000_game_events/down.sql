@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS game_event_game_index;
+
+DROP TABLE IF EXISTS game_event;
@@ -0,0 +1,15 @@
+CREATE TABLE game_event (
+    id              INT GENERATED ALWAYS AS IDENTITY,
+    game            INT NOT NULL
+                    REFERENCES game(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    -- Only the recipient may see the event, everyone in the game does when NULL
+    recipient       INT
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    payload         JSONB NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX game_event_game_index ON game_event(game, id);
@@ -0,0 +1,23 @@
+-- These events didn't exist before
+DELETE FROM game_event WHERE kind IN ('GAME_CREATED', 'KILL_DISPUTED', 'KILL_REJECTED');
+
+ALTER TABLE game_event ADD COLUMN payload JSONB;
+
+UPDATE game_event e SET payload = jsonb_strip_nulls(jsonb_build_object(
+    'event', e.kind,
+    'codename', CASE WHEN e.kind IN ('PLAYER_JOINED', 'PLAYER_LEFT') THEN actor.codename END,
+    'victim', CASE WHEN e.kind = 'KILL' THEN subject.codename END,
+    'winner', CASE WHEN e.kind = 'GAME_ENDED' THEN subject.codename END
+))
+FROM game_event g
+    LEFT JOIN playergame actor ON actor.game = g.game AND actor.player = g.actor
+    LEFT JOIN playergame subject ON subject.game = g.game AND subject.player = g.subject
+WHERE g.id = e.id;
+
+ALTER TABLE game_event
+    ALTER COLUMN payload SET NOT NULL,
+    DROP COLUMN kind,
+    DROP COLUMN actor,
+    DROP COLUMN subject;
+
+DROP TYPE IF EXISTS game_event_kind_t;
@@ -0,0 +1,43 @@
+CREATE TYPE game_event_kind_t AS ENUM (
+    'GAME_CREATED',
+    'PLAYER_JOINED',
+    'PLAYER_LEFT',
+    'GAME_STARTED',
+    'GAME_PAUSED',
+    'GAME_RESUMED',
+    'KILL_CLAIMED',
+    'KILL_DISPUTED',
+    'KILL_REJECTED',
+    'KILL',
+    'TARGET_CHANGED',
+    'GAME_ENDED'
+);
+
+-- The player behind the event (e.g. the killer) and the one it happened to (e.g. the victim)
+ALTER TABLE game_event
+    ADD COLUMN kind     game_event_kind_t,
+    ADD COLUMN actor    INT
+                        REFERENCES player(id)
+                            ON UPDATE CASCADE ON DELETE NO ACTION,
+    ADD COLUMN subject  INT
+                        REFERENCES player(id)
+                            ON UPDATE CASCADE ON DELETE NO ACTION;
+
+UPDATE game_event SET kind = (payload->>'event')::game_event_kind_t;
+
+-- Players used to be logged by codename
+UPDATE game_event e SET actor = pg.player
+    FROM playergame pg
+    WHERE pg.game = e.game AND pg.codename = e.payload->>'codename';
+
+UPDATE game_event e SET subject = pg.player
+    FROM playergame pg
+    WHERE pg.game = e.game AND pg.codename = COALESCE(e.payload->>'victim', e.payload->>'winner');
+
+-- Private events were always about their recipient
+UPDATE game_event SET actor = recipient WHERE kind = 'TARGET_CHANGED';
+UPDATE game_event SET subject = recipient WHERE kind = 'KILL_CLAIMED';
+
+ALTER TABLE game_event
+    ALTER COLUMN kind SET NOT NULL,
+    DROP COLUMN payload;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS admin_action;
+
+DROP TYPE IF EXISTS admin_action_kind_t;
@@ -0,0 +1,27 @@
+CREATE TYPE admin_action_kind_t AS ENUM (
+    'END_GAME',
+    'PAUSE_GAME',
+    'RESUME_GAME',
+    'KICK_PLAYER',
+    'CHANGE_ROLE',
+    'RESOLVE_KILL'
+);
+
+-- Audit log of everything done through the admin API
+CREATE TABLE admin_action (
+    id              INT GENERATED ALWAYS AS IDENTITY,
+    admin           INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    kind            admin_action_kind_t NOT NULL,
+    -- The game and player the action was about, when there is one
+    game            INT
+                    REFERENCES game(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    player          INT
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    details         VARCHAR,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (id)
+);
@@ -0,0 +1,14 @@
+-- Players the owner of a game doesn't want back in it
+CREATE TABLE game_ban (
+    game            INT NOT NULL
+                    REFERENCES game(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    player          INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    banned_by       INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (game, player)
+);
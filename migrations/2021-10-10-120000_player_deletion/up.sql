@@ -0,0 +1,2 @@
+-- Deleted accounts are anonymized rather than removed, so that game history stays consistent
+ALTER TABLE player ADD COLUMN deleted_at TIMESTAMPTZ;
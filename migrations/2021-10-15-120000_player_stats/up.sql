@@ -0,0 +1,41 @@
+-- Career statistics of every player over the games which are over. Refreshed in the
+-- background by the server, so that reading them doesn't get slower as history grows.
+CREATE MATERIALIZED VIEW player_stats AS
+WITH played AS (
+    SELECT pg.player, pg.game,
+        g.winner = pg.player AS won,
+        d.end_time AS died_at,
+        -- Players who left a game are not counted as having survived it
+        CASE WHEN pg.status <> 'LEFT_GAME' OR d.end_time IS NOT NULL THEN
+            EXTRACT(EPOCH FROM COALESCE(d.end_time, g.end_time)
+                - GREATEST(g.start_time, pg.joined_at))
+        END AS survival_seconds
+    FROM playergame pg
+    INNER JOIN game g ON g.id = pg.game
+    LEFT JOIN assignment d ON d.game = pg.game
+        AND d.target = pg.player
+        AND d.status = 'KILL_SUCCESS'
+    WHERE g.status = 'FINISHED' AND g.start_time IS NOT NULL
+),
+kills AS (
+    SELECT a.assassin AS player, COUNT(*) AS kills,
+        AVG(EXTRACT(EPOCH FROM a.end_time - a.start_time)) AS seconds_to_kill
+    FROM assignment a
+    INNER JOIN game g ON g.id = a.game
+    WHERE g.status = 'FINISHED' AND a.status = 'KILL_SUCCESS'
+    GROUP BY a.assassin
+)
+SELECT p.id AS player,
+    COUNT(pl.game) AS games_played,
+    COUNT(pl.game) FILTER (WHERE pl.won) AS wins,
+    COALESCE(MAX(k.kills), 0) AS kills,
+    COUNT(pl.died_at) AS deaths,
+    MAX(pl.survival_seconds)::BIGINT AS longest_survival_seconds,
+    MAX(k.seconds_to_kill)::BIGINT AS average_seconds_to_kill
+FROM player p
+LEFT JOIN played pl ON pl.player = p.id
+LEFT JOIN kills k ON k.player = p.id
+GROUP BY p.id;
+
+-- Needed to refresh the view without blocking readers
+CREATE UNIQUE INDEX player_stats_player_index ON player_stats(player);
@@ -0,0 +1,40 @@
+-- Rankings of the players over the games which are over, updated whenever a game finishes.
+-- Each season has its own rows, next to the all-time ones.
+CREATE TABLE leaderboard_entry (
+    season          VARCHAR NOT NULL,
+    player          INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    games_played    INT NOT NULL DEFAULT 0,
+    wins            INT NOT NULL DEFAULT 0,
+    kills           INT NOT NULL DEFAULT 0,
+    rating          INT NOT NULL DEFAULT 1000,
+    PRIMARY KEY (season, player)
+);
+
+CREATE INDEX leaderboard_entry_kills_index ON leaderboard_entry(season, kills DESC);
+CREATE INDEX leaderboard_entry_wins_index ON leaderboard_entry(season, wins DESC);
+CREATE INDEX leaderboard_entry_rating_index ON leaderboard_entry(season, rating DESC);
+
+-- Past games count towards the kills and wins, but everybody starts from the initial rating
+WITH played AS (
+    SELECT pg.player,
+        TO_CHAR(g.end_time AT TIME ZONE 'UTC', 'YYYY-"Q"Q') AS season,
+        CASE WHEN g.winner = pg.player THEN 1 ELSE 0 END AS won,
+        (
+            SELECT COUNT(*)
+            FROM assignment a
+            WHERE a.game = g.id AND a.assassin = pg.player AND a.status = 'KILL_SUCCESS'
+        ) AS kills
+    FROM playergame pg
+    INNER JOIN game g ON g.id = pg.game
+    WHERE g.status = 'FINISHED' AND g.start_time IS NOT NULL
+)
+INSERT INTO leaderboard_entry (season, player, games_played, wins, kills)
+SELECT season, player, COUNT(*), SUM(won), SUM(kills)
+FROM played
+GROUP BY season, player
+UNION ALL
+SELECT 'all-time', player, COUNT(*), SUM(won), SUM(kills)
+FROM played
+GROUP BY player;
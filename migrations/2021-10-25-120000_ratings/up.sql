@@ -0,0 +1,23 @@
+-- Skill rating of the players, updated whenever a game finishes
+ALTER TABLE player ADD COLUMN rating INT NOT NULL DEFAULT 1000;
+
+-- The all-time leaderboard already rated the games finished since it was introduced
+UPDATE player
+SET rating = leaderboard_entry.rating
+FROM leaderboard_entry
+WHERE leaderboard_entry.player = player.id AND leaderboard_entry.season = 'all-time';
+
+CREATE TABLE rating_history (
+    player          INT NOT NULL
+                    REFERENCES player(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    game            INT NOT NULL
+                    REFERENCES game(id)
+                        ON UPDATE CASCADE ON DELETE NO ACTION,
+    rating_before   INT NOT NULL,
+    rating_after    INT NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (player, game)
+);
+
+CREATE INDEX rating_history_player_index ON rating_history(player, created_at DESC);
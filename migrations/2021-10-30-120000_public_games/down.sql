@@ -0,0 +1 @@
+ALTER TABLE game_settings DROP COLUMN is_public;
@@ -0,0 +1,2 @@
+-- Public games are listed in the lobbies, so that players can join without being given the code
+ALTER TABLE game_settings ADD COLUMN is_public BOOLEAN NOT NULL DEFAULT FALSE;
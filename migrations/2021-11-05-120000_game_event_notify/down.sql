@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS notify_game_event ON game_event;
+DROP FUNCTION IF EXISTS notify_game_event();
@@ -0,0 +1,12 @@
+-- Announces every logged event once its transaction commits, so that each server
+-- replica can deliver it to the players connected to it
+CREATE OR REPLACE FUNCTION notify_game_event() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('game_event', NEW.id::TEXT);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notify_game_event
+    AFTER INSERT ON game_event
+    FOR EACH ROW EXECUTE PROCEDURE notify_game_event();
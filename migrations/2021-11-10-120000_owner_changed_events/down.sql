@@ -0,0 +1,24 @@
+-- Values can't be removed from an enum, so the type is built again without it
+DELETE FROM game_event WHERE kind = 'OWNER_CHANGED';
+
+ALTER TYPE game_event_kind_t RENAME TO game_event_kind_t_old;
+
+CREATE TYPE game_event_kind_t AS ENUM (
+    'GAME_CREATED',
+    'PLAYER_JOINED',
+    'PLAYER_LEFT',
+    'GAME_STARTED',
+    'GAME_PAUSED',
+    'GAME_RESUMED',
+    'KILL_CLAIMED',
+    'KILL_DISPUTED',
+    'KILL_REJECTED',
+    'KILL',
+    'TARGET_CHANGED',
+    'GAME_ENDED'
+);
+
+ALTER TABLE game_event
+    ALTER COLUMN kind TYPE game_event_kind_t USING kind::TEXT::game_event_kind_t;
+
+DROP TYPE game_event_kind_t_old;
@@ -0,0 +1 @@
+ALTER TYPE game_event_kind_t ADD VALUE 'OWNER_CHANGED';
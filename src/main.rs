@@ -8,6 +8,7 @@ use assassin_server::routes;
 use assassin_server::utils::auth;
 use assassin_server::utils::config::CFG;
 use assassin_server::utils::logging;
+use assassin_server::utils::scheduler;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -25,11 +26,18 @@ async fn main() -> std::io::Result<()> {
 
     db::init();
 
+    scheduler::spawn_game_expiry();
+    scheduler::spawn_stats_refresh();
+    scheduler::spawn_key_refresh();
+    scheduler::spawn_event_relay();
+
     let mut server = HttpServer::new(move || {
         let auth = HttpAuthentication::bearer(auth::bearer_auth_validator);
         App::new()
             .wrap(TracingLogger)
             .configure(routes::health::config)
+            .configure(routes::dev::config)
+            .configure(routes::media::config)
             .default_service(web::route().to(|| HttpResponse::NotFound()))
             .service(
                 //Protected routes in the official API
@@ -37,7 +45,11 @@ async fn main() -> std::io::Result<()> {
                     .wrap(auth)
                     .configure(routes::debug::config)
                     .configure(routes::auth::config)
-                    .configure(routes::game::config),
+                    .configure(routes::game::config)
+                    .configure(routes::events::config)
+                    .configure(routes::profile::config)
+                    .configure(routes::leaderboard::config)
+                    .configure(routes::admin::config),
             )
     });
 
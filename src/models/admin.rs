@@ -0,0 +1,364 @@
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::{Insertable, Queryable};
+use futures_util::future::{err, ok, Ready};
+use serde::Serialize;
+use tracing::info;
+
+use crate::db;
+use crate::models::api_errors::ApiError;
+use crate::models::constants::MAX_PAGE_SIZE;
+use crate::models::enums::{AdminActionKind, GameStatus, Role};
+use crate::models::game::Game;
+use crate::models::kill_claim::DisputedKill;
+use crate::models::model_errors::{ModelError, Result};
+use crate::models::player::Player;
+
+use crate::schema::*;
+
+/// A player with the admin role. Extracting it from a request fails for everybody else.
+#[derive(Debug)]
+pub struct AdminPlayer(pub Player);
+
+#[derive(Debug, Insertable)]
+#[table_name = "admin_action"]
+struct NewAdminAction {
+    admin: i32,
+    kind: AdminActionKind,
+    game: Option<i32>,
+    player: Option<i32>,
+    details: Option<String>,
+}
+
+/// An entry of the audit log of the admin API
+#[derive(Debug, Serialize, Queryable)]
+pub struct AdminAction {
+    pub id: i32,
+    pub admin: i32,
+    pub kind: AdminActionKind,
+    pub game: Option<i32>,
+    pub player: Option<i32>,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerPage {
+    pub players: Vec<Player>,
+    pub next_cursor: Option<i32>, // Pass it as `after` to get the following page
+}
+
+#[derive(Debug, Serialize)]
+pub struct GamePage {
+    pub games: Vec<Game>,
+    pub next_cursor: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditPage {
+    pub actions: Vec<AdminAction>,
+    pub next_cursor: Option<i32>,
+}
+
+impl AdminPlayer {
+    /// Players whose nickname or email contains the search string, by registration order
+    pub fn players(&self, search: Option<&str>, after: i32, limit: i64) -> Result<PlayerPage> {
+        check_page_size(limit)?;
+
+        let conn = db::connection()?;
+
+        let mut query = player::table
+            .filter(player::id.gt(after))
+            .order(player::id.asc())
+            .limit(limit)
+            .into_boxed();
+
+        if let Some(search) = search {
+            let pattern = like_pattern(search);
+            query = query.filter(
+                player::nickname
+                    .ilike(pattern.clone())
+                    .or(player::email.ilike(pattern)),
+            );
+        }
+
+        let players: Vec<Player> = query.load(&conn)?;
+        let next_cursor = next_cursor(&players, limit, |p| p.id);
+
+        Ok(PlayerPage {
+            players,
+            next_cursor,
+        })
+    }
+
+    /// Games whose code or name contains the search string, by creation order
+    pub fn games(
+        &self,
+        search: Option<&str>,
+        status: Option<GameStatus>,
+        after: i32,
+        limit: i64,
+    ) -> Result<GamePage> {
+        check_page_size(limit)?;
+
+        let conn = db::connection()?;
+
+        let mut query = game::table
+            .filter(game::id.gt(after))
+            .order(game::id.asc())
+            .limit(limit)
+            .into_boxed();
+
+        if let Some(search) = search {
+            let pattern = like_pattern(search);
+            query = query.filter(
+                game::code
+                    .ilike(pattern.clone())
+                    .or(game::name.ilike(pattern)),
+            );
+        }
+
+        if let Some(status) = status {
+            query = query.filter(game::status.eq(status));
+        }
+
+        let games: Vec<Game> = query.load(&conn)?;
+        let next_cursor = next_cursor(&games, limit, |g| g.id);
+
+        Ok(GamePage { games, next_cursor })
+    }
+
+    /// The audit log, oldest actions first
+    pub fn actions(&self, after: i32, limit: i64) -> Result<AuditPage> {
+        check_page_size(limit)?;
+
+        let conn = db::connection()?;
+
+        let actions: Vec<AdminAction> = admin_action::table
+            .filter(admin_action::id.gt(after))
+            .order(admin_action::id.asc())
+            .limit(limit)
+            .load(&conn)?;
+        let next_cursor = next_cursor(&actions, limit, |a| a.id);
+
+        Ok(AuditPage {
+            actions,
+            next_cursor,
+        })
+    }
+
+    /// Ends the game right away. Games which had started get a winner out of the standings.
+    pub fn end_game(&self, code: &String) -> Result<()> {
+        let conn = db::connection()?;
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+
+            match requested_game.status {
+                GameStatus::FINISHED => {
+                    info!("Game {} is already finished", code);
+                    return Err(ModelError::GameNotActive);
+                }
+                GameStatus::WAITING_FOR_PLAYERS => requested_game.finish(&conn, None)?,
+                _ => requested_game.finish_with_standings(&conn)?,
+            }
+
+            self.record(
+                &conn,
+                AdminActionKind::END_GAME,
+                Some(requested_game.id),
+                None,
+                None,
+            )
+        })
+    }
+
+    pub fn pause_game(&self, code: &String) -> Result<()> {
+        let conn = db::connection()?;
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+            requested_game.pause(&conn, self.0.id)?;
+
+            self.record(
+                &conn,
+                AdminActionKind::PAUSE_GAME,
+                Some(requested_game.id),
+                None,
+                None,
+            )
+        })
+    }
+
+    pub fn resume_game(&self, code: &String) -> Result<()> {
+        let conn = db::connection()?;
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+            requested_game.resume(&conn, self.0.id)?;
+
+            self.record(
+                &conn,
+                AdminActionKind::RESUME_GAME,
+                Some(requested_game.id),
+                None,
+                None,
+            )
+        })
+    }
+
+    /// Takes the player out of the game, as if they had left it
+    pub fn kick_player(&self, code: &String, player_id: i32) -> Result<()> {
+        let conn = db::connection()?;
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+
+            if requested_game.status == GameStatus::FINISHED {
+                info!("Game {} is already finished", code);
+                return Err(ModelError::GameNotActive);
+            }
+
+            requested_game.remove_player(&conn, player_id)?;
+
+            self.record(
+                &conn,
+                AdminActionKind::KICK_PLAYER,
+                Some(requested_game.id),
+                Some(player_id),
+                None,
+            )
+        })
+    }
+
+    pub fn set_role(&self, player_id: i32, role: Role) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            // Lock the admins, so that concurrent demotions can't get rid of all of them
+            let admins: Vec<i32> = player::table
+                .filter(player::role.eq(Role::ADMIN))
+                .select(player::id)
+                .for_update()
+                .load(&conn)?;
+
+            if role != Role::ADMIN && admins == [player_id] {
+                info!("User {} is the last admin. Cannot change role", player_id);
+                return Err(ModelError::LastAdmin);
+            }
+
+            let updated = diesel::update(player::table.find(player_id))
+                .set(player::role.eq(role.clone()))
+                .execute(&conn)?;
+
+            if updated == 0 {
+                info!("User {} does not exist. Cannot change role", player_id);
+                return Err(ModelError::PlayerNotFound);
+            }
+
+            info!(
+                "Admin {} changed the role of user {} to {:?}",
+                self.0.id, player_id, role
+            );
+
+            self.record(
+                &conn,
+                AdminActionKind::CHANGE_ROLE,
+                None,
+                Some(player_id),
+                Some(format!("{:?}", role)),
+            )
+        })
+    }
+
+    pub fn disputed_kills(&self, code: &String) -> Result<Vec<DisputedKill>> {
+        let conn = db::connection()?;
+
+        let requested_game: Game = game::table
+            .filter(game::code.eq(code))
+            .first(&conn)
+            .map_err(|_| ModelError::GameNotFound)?;
+
+        requested_game.disputed_kills(&conn)
+    }
+
+    /// Settles a disputed kill in place of the game owner
+    pub fn resolve_kill(&self, code: &String, claim_id: i32, confirm: bool) -> Result<()> {
+        let conn = db::connection()?;
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+            requested_game.resolve_dispute(&conn, claim_id, confirm)?;
+
+            let outcome = if confirm { "confirmed" } else { "rejected" };
+
+            self.record(
+                &conn,
+                AdminActionKind::RESOLVE_KILL,
+                Some(requested_game.id),
+                None,
+                Some(format!("Kill claim {} {}", claim_id, outcome)),
+            )
+        })
+    }
+
+    /// Writes the action to the audit log, as part of the transaction that performed it
+    fn record(
+        &self,
+        conn: &PgConnection,
+        kind: AdminActionKind,
+        game: Option<i32>,
+        player: Option<i32>,
+        details: Option<String>,
+    ) -> Result<()> {
+        diesel::insert_into(admin_action::table)
+            .values(NewAdminAction {
+                admin: self.0.id,
+                kind,
+                game,
+                player,
+                details,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+impl FromRequest for AdminPlayer {
+    type Error = ApiError;
+    type Future = Ready<std::result::Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        match Player::from_request(req, payload).into_inner() {
+            Ok(player) if player.role == Role::ADMIN => ok(AdminPlayer(player)),
+            Ok(player) => {
+                info!("User {} is not an admin", player.id);
+                err(ModelError::NotAdmin.into())
+            }
+            Err(e) => err(e),
+        }
+    }
+}
+
+fn check_page_size(limit: i64) -> Result<()> {
+    if limit <= 0 || limit > MAX_PAGE_SIZE {
+        return Err(ModelError::InvalidPagination);
+    }
+    Ok(())
+}
+
+/// Cursor of the page after the given one, unless it is the last
+fn next_cursor<T>(items: &[T], limit: i64, id: impl Fn(&T) -> i32) -> Option<i32> {
+    match items.last() {
+        Some(last) if items.len() as i64 == limit => Some(id(last)),
+        _ => None,
+    }
+}
+
+/// Matches the strings containing the given one, taken literally
+pub(crate) fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
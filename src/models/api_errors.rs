@@ -3,6 +3,7 @@ use serde::Serialize;
 use thiserror::Error;
 
 use crate::models::model_errors::ModelError;
+use crate::utils::auth::AuthError;
 
 #[derive(Debug, Error)]
 pub enum ApiError {
@@ -50,11 +51,41 @@ impl From<ModelError> for ApiError {
             | ModelError::GameNotStarted
             | ModelError::NoCurrentTarget
             | ModelError::GameNotFound
+            | ModelError::GameNotActive
+            | ModelError::GamePaused
+            | ModelError::GameNotPaused
+            | ModelError::GameNotFinished
+            | ModelError::InvalidSettings
+            | ModelError::GameAlreadyStarted
+            | ModelError::NotEnoughPlayers
+            | ModelError::KillAlreadyClaimed
+            | ModelError::NoPendingKillClaim
+            | ModelError::KillClaimNotFound
+            | ModelError::InvalidPagination
+            | ModelError::PlayerNotFound
+            | ModelError::CannotModerateSelf
+            | ModelError::LastAdmin
+            | ModelError::BannedFromGame
+            | ModelError::GameFull
+            | ModelError::NicknameTaken
+            | ModelError::InvalidNickname
+            | ModelError::InvalidPicture
             | ModelError::AlreadyRegistered => Self::BadRequest(e.error_code()),
             ModelError::DatabaseError | ModelError::UnknownError(_) => {
                 Self::InternalServerError(e.error_code())
             }
-            ModelError::NotRegistered => Self::Unauthorized(e.error_code()),
+            ModelError::NotRegistered | ModelError::NotGameOwner | ModelError::NotAdmin => {
+                Self::Unauthorized(e.error_code())
+            }
+        }
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::Internal => Self::InternalServerError(e.error_code()),
+            _ => Self::Unauthorized(e.error_code()),
         }
     }
 }
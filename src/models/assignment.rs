@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::{Associations, Identifiable, Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::enums::TargetStatus;
+use crate::models::game::Game;
+
+use crate::schema::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[table_name = "assignment"]
+pub struct NewAssignment {
+    pub assassin: i32,
+    pub target: i32,
+    pub game: i32,
+    pub status: TargetStatus,
+}
+
+#[derive(Debug, Serialize, Associations, Deserialize, Queryable, Identifiable)]
+#[belongs_to(Game, foreign_key = "game")]
+#[primary_key(assassin, target, game)]
+#[table_name = "assignment"]
+pub struct Assignment {
+    pub assassin: i32,
+    pub target: i32,
+    pub game: i32,
+    pub status: TargetStatus,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Assignment {
+    /// Fetches (and locks) the current assignment of the given assassin, if any
+    pub fn current_of(
+        conn: &PgConnection,
+        game_id: i32,
+        assassin: i32,
+    ) -> QueryResult<Option<Assignment>> {
+        assignment::table
+            .filter(assignment::game.eq(game_id))
+            .filter(assignment::assassin.eq(assassin))
+            .filter(assignment::status.eq(TargetStatus::CURRENT))
+            .for_update()
+            .first(conn)
+            .optional()
+    }
+
+    /// Fetches (and locks) the current assignment targeting the given player, if any
+    pub fn current_on(
+        conn: &PgConnection,
+        game_id: i32,
+        target: i32,
+    ) -> QueryResult<Option<Assignment>> {
+        assignment::table
+            .filter(assignment::game.eq(game_id))
+            .filter(assignment::target.eq(target))
+            .filter(assignment::status.eq(TargetStatus::CURRENT))
+            .for_update()
+            .first(conn)
+            .optional()
+    }
+
+    /// Closes the assignment with the given outcome
+    pub fn close(&self, conn: &PgConnection, status: TargetStatus) -> QueryResult<()> {
+        diesel::update(self)
+            .set((
+                assignment::status.eq(status),
+                assignment::end_time.eq(chrono::offset::Utc::now()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+/// Builds the assassin -> target ring for the given agents: each agent hunts the next one
+/// in the list, and the last one hunts the first. Callers are expected to shuffle the agents.
+pub fn make_ring(game_id: i32, agents: &[i32]) -> Vec<NewAssignment> {
+    agents
+        .iter()
+        .zip(agents.iter().cycle().skip(1))
+        .map(|(&assassin, &target)| NewAssignment {
+            assassin,
+            target,
+            game: game_id,
+            status: TargetStatus::CURRENT,
+        })
+        .collect()
+}
@@ -1 +1,32 @@
 pub const DEFAULT_MAX_PLAYERS: i32 = 12;
+pub const DEFAULT_MIN_PLAYERS: i32 = 2;
+pub const DEFAULT_GAME_DURATION_MINUTES: i32 = 3 * 24 * 60;
+
+// Bounds for the settings chosen by the game owner
+pub const MAX_PLAYERS_LIMIT: i32 = 100;
+pub const MIN_GAME_DURATION_MINUTES: i32 = 10;
+pub const MAX_GAME_DURATION_MINUTES: i32 = 30 * 24 * 60;
+
+// Nicknames are shown next to pictures on small screens
+pub const MAX_NICKNAME_LENGTH: usize = 32;
+
+// Key of the Postgres advisory lock taken while expiring games
+pub const GAME_EXPIRY_LOCK_ID: i64 = 0x6173_7361_7373_696e;
+
+// Unanswered kill claims are automatically confirmed after this many minutes
+pub const KILL_CONFIRMATION_TIMEOUT_MINUTES: i64 = 60;
+
+// Bounds on the size of the pages served by paginated endpoints
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+// Points awarded in the final standings of a game, unless the owner chooses otherwise
+pub const DEFAULT_SCORE_PER_KILL: i32 = 10;
+pub const DEFAULT_SCORE_FOR_SURVIVING: i32 = 5;
+pub const DEFAULT_SCORE_FOR_WINNING: i32 = 20;
+
+// Rating of the players who never finished a game
+pub const INITIAL_RATING: i32 = 1000;
+
+// Rating changes shown along with the info of a player
+pub const RECENT_RATING_CHANGES: i64 = 20;
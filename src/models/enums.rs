@@ -35,7 +35,27 @@ pub enum TargetStatus {
     GAME_END,
 }
 
-#[derive(Debug, Clone, DbEnum, Serialize, Deserialize)]
+#[derive(Debug, Clone, DbEnum, Serialize, Deserialize, PartialEq)]
+#[PgType = "kill_claim_status_t"]
+#[DieselType = "Kill_claim_status_t"]
+#[DbValueStyle = "verbatim"]
+pub enum KillClaimStatus {
+    PENDING,
+    CONFIRMED,
+    DISPUTED,
+    REJECTED,
+}
+
+#[derive(Debug, Clone, DbEnum, Serialize, Deserialize, PartialEq)]
+#[PgType = "kill_confirmation_t"]
+#[DieselType = "Kill_confirmation_t"]
+#[DbValueStyle = "verbatim"]
+pub enum KillConfirmation {
+    ASSASSIN,
+    VICTIM,
+}
+
+#[derive(Debug, Clone, DbEnum, Serialize, Deserialize, PartialEq)]
 #[PgType = "role_t"]
 #[DieselType = "Role_t"]
 #[DbValueStyle = "verbatim"]
@@ -44,6 +64,39 @@ pub enum Role {
     ADMIN,
 }
 
+#[derive(Debug, Clone, DbEnum, Serialize, Deserialize, PartialEq)]
+#[PgType = "game_event_kind_t"]
+#[DieselType = "Game_event_kind_t"]
+#[DbValueStyle = "verbatim"]
+pub enum GameEventKind {
+    GAME_CREATED,
+    PLAYER_JOINED,
+    PLAYER_LEFT,
+    GAME_STARTED,
+    GAME_PAUSED,
+    GAME_RESUMED,
+    KILL_CLAIMED,
+    KILL_DISPUTED,
+    KILL_REJECTED,
+    KILL,
+    TARGET_CHANGED,
+    GAME_ENDED,
+    OWNER_CHANGED,
+}
+
+#[derive(Debug, Clone, DbEnum, Serialize, Deserialize, PartialEq)]
+#[PgType = "admin_action_kind_t"]
+#[DieselType = "Admin_action_kind_t"]
+#[DbValueStyle = "verbatim"]
+pub enum AdminActionKind {
+    END_GAME,
+    PAUSE_GAME,
+    RESUME_GAME,
+    KICK_PLAYER,
+    CHANGE_ROLE,
+    RESOLVE_KILL,
+}
+
 impl Default for Role {
     fn default() -> Self {
         Role::USER
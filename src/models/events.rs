@@ -0,0 +1,422 @@
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::{Insertable, Queryable};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use lazy_static::lazy_static;
+use postgres::fallible_iterator::FallibleIterator;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::db;
+use crate::models::constants::MAX_PAGE_SIZE;
+use crate::models::enums::{GameEventKind, GameStatus, Role};
+use crate::models::game::Game;
+use crate::models::model_errors::{ModelError, Result};
+use crate::models::player::Player;
+
+use crate::schema::*;
+
+/// Something that happened in a game, as reported by `models::game`
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    GameCreated { owner: i32 },
+    PlayerJoined { player: i32 },
+    PlayerLeft { player: i32 },
+    GameStarted { by: i32 },
+    GamePaused { by: i32 },
+    GameResumed { by: i32 },
+    KillClaimed { assassin: i32, target: i32 },
+    KillDisputed { assassin: i32, target: i32 },
+    KillRejected { assassin: i32, target: i32 },
+    Kill { assassin: i32, target: i32 },
+    TargetChanged { assassin: i32, target: i32 },
+    GameEnded { winner: Option<i32> },
+    OwnerChanged { from: i32, to: i32 },
+}
+
+impl GameEvent {
+    /// Splits the event into what gets stored in the log: its kind, actor and subject
+    fn into_parts(self) -> (GameEventKind, Option<i32>, Option<i32>) {
+        use GameEvent::*;
+
+        match self {
+            GameCreated { owner } => (GameEventKind::GAME_CREATED, Some(owner), None),
+            PlayerJoined { player } => (GameEventKind::PLAYER_JOINED, Some(player), None),
+            PlayerLeft { player } => (GameEventKind::PLAYER_LEFT, Some(player), None),
+            GameStarted { by } => (GameEventKind::GAME_STARTED, Some(by), None),
+            GamePaused { by } => (GameEventKind::GAME_PAUSED, Some(by), None),
+            GameResumed { by } => (GameEventKind::GAME_RESUMED, Some(by), None),
+            KillClaimed { assassin, target } => {
+                (GameEventKind::KILL_CLAIMED, Some(assassin), Some(target))
+            }
+            KillDisputed { assassin, target } => {
+                (GameEventKind::KILL_DISPUTED, Some(assassin), Some(target))
+            }
+            KillRejected { assassin, target } => {
+                (GameEventKind::KILL_REJECTED, Some(assassin), Some(target))
+            }
+            Kill { assassin, target } => (GameEventKind::KILL, Some(assassin), Some(target)),
+            TargetChanged { assassin, target } => {
+                (GameEventKind::TARGET_CHANGED, Some(assassin), Some(target))
+            }
+            GameEnded { winner } => (GameEventKind::GAME_ENDED, None, winner),
+            OwnerChanged { from, to } => (GameEventKind::OWNER_CHANGED, Some(from), Some(to)),
+        }
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "game_event"]
+struct NewLoggedEvent {
+    game: i32,
+    recipient: Option<i32>,
+    kind: GameEventKind,
+    actor: Option<i32>,
+    subject: Option<i32>,
+}
+
+/// An entry of the `game_event` log
+#[derive(Debug, Clone, Queryable)]
+pub struct LoggedEvent {
+    pub id: i32,
+    pub game: i32,
+    pub recipient: Option<i32>, // None means everyone in the game
+    pub created_at: DateTime<Utc>,
+    pub kind: GameEventKind,
+    pub actor: Option<i32>,
+    pub subject: Option<i32>,
+}
+
+/// An event the way a given player gets to see it: players are shown by codename,
+/// and whoever is behind a kill stays hidden while the game goes on.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventView {
+    pub id: i32,
+    pub event: GameEventKind,
+    pub actor: Option<String>,
+    pub subject: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventPage {
+    pub events: Vec<EventView>,
+    pub next_cursor: Option<i32>, // Pass it as `after` to get the following page
+}
+
+/// Who is looking at the log
+struct Viewer {
+    player: i32,
+    is_admin: bool,
+}
+
+impl LoggedEvent {
+    fn view(
+        &self,
+        viewer: &Viewer,
+        game_finished: bool,
+        codenames: &HashMap<i32, String>,
+    ) -> EventView {
+        let is_kill = matches!(
+            self.kind,
+            GameEventKind::KILL
+                | GameEventKind::KILL_CLAIMED
+                | GameEventKind::KILL_DISPUTED
+                | GameEventKind::KILL_REJECTED
+        );
+
+        // The owner needs to know both sides of a dispute to settle it
+        let reveal_actor = !is_kill
+            || game_finished
+            || viewer.is_admin
+            || self.actor == Some(viewer.player)
+            || (self.kind == GameEventKind::KILL_DISPUTED && self.recipient == Some(viewer.player));
+
+        let codename = |player: Option<i32>| player.and_then(|p| codenames.get(&p).cloned());
+
+        EventView {
+            id: self.id,
+            event: self.kind.clone(),
+            actor: if reveal_actor {
+                codename(self.actor)
+            } else {
+                None
+            },
+            subject: codename(self.subject),
+            created_at: self.created_at,
+        }
+    }
+}
+
+struct Subscriber {
+    player: i32,
+    sender: UnboundedSender<EventView>,
+}
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<HashMap<i32, Vec<Subscriber>>> = Mutex::new(HashMap::new());
+}
+
+/// Postgres channel on which the id of every logged event gets announced, by a trigger on
+/// the `game_event` table
+const CHANNEL: &str = "game_event";
+
+/// Logs an event for everyone in the game. It is delivered once the transaction commits.
+pub fn notify_game(conn: &PgConnection, game: i32, event: GameEvent) -> QueryResult<()> {
+    log(conn, game, None, event)
+}
+
+/// Logs an event for a single player of the game. It is delivered once the transaction commits.
+pub fn notify_player(
+    conn: &PgConnection,
+    game: i32,
+    player: i32,
+    event: GameEvent,
+) -> QueryResult<()> {
+    log(conn, game, Some(player), event)
+}
+
+fn log(
+    conn: &PgConnection,
+    game: i32,
+    recipient: Option<i32>,
+    event: GameEvent,
+) -> QueryResult<()> {
+    let (kind, actor, subject) = event.into_parts();
+
+    diesel::insert_into(game_event::table)
+        .values(NewLoggedEvent {
+            game,
+            recipient,
+            kind,
+            actor,
+            subject,
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn codenames_of(
+    conn: &PgConnection,
+    game: i32,
+    players: &[i32],
+) -> QueryResult<HashMap<i32, String>> {
+    Ok(playergame::table
+        .filter(playergame::game.eq(game))
+        .filter(playergame::player.eq_any(players))
+        .select((playergame::player, playergame::codename))
+        .load::<(i32, String)>(conn)?
+        .into_iter()
+        .collect())
+}
+
+/// Loads the events logged after the given one which the viewer is allowed to see
+fn load(
+    conn: &PgConnection,
+    game: &Game,
+    viewer: &Viewer,
+    after: i32,
+    limit: i64,
+) -> QueryResult<Vec<EventView>> {
+    let mut query = game_event::table
+        .filter(game_event::game.eq(game.id))
+        .filter(game_event::id.gt(after))
+        .order(game_event::id.asc())
+        .limit(limit)
+        .into_boxed();
+
+    if !viewer.is_admin {
+        query = query.filter(
+            game_event::recipient
+                .is_null()
+                .or(game_event::recipient.eq(viewer.player)),
+        );
+    }
+
+    let events: Vec<LoggedEvent> = query.load(conn)?;
+
+    let codenames: HashMap<i32, String> = playergame::table
+        .filter(playergame::game.eq(game.id))
+        .select((playergame::player, playergame::codename))
+        .load::<(i32, String)>(conn)?
+        .into_iter()
+        .collect();
+
+    let game_finished = game.status == GameStatus::FINISHED;
+
+    Ok(events
+        .iter()
+        .map(|event| event.view(viewer, game_finished, &codenames))
+        .collect())
+}
+
+/// Fetches everything a member of the game missed since the given event
+pub fn logged_since(game: &Game, player: i32, last_event_id: i32) -> Result<Vec<EventView>> {
+    let conn = db::connection()?;
+
+    let viewer = Viewer {
+        player,
+        is_admin: false,
+    };
+
+    Ok(load(&conn, game, &viewer, last_event_id, i64::MAX)?)
+}
+
+/// Fetches a page of the game's log. Admins get to see every event of any game,
+/// while members only see the public events and their own.
+pub fn page(code: &String, player: &Player, after: i32, limit: i64) -> Result<EventPage> {
+    let is_admin = player.role == Role::ADMIN;
+
+    if limit <= 0 || limit > MAX_PAGE_SIZE {
+        return Err(ModelError::InvalidPagination);
+    }
+
+    let conn = db::connection()?;
+
+    let requested_game = if is_admin {
+        game::table
+            .filter(game::code.eq(code))
+            .first(&conn)
+            .map_err(|_| ModelError::GameNotFound)?
+    } else {
+        Game::find_as_member(code, player.id)?
+    };
+
+    let viewer = Viewer {
+        player: player.id,
+        is_admin,
+    };
+
+    let events = load(&conn, &requested_game, &viewer, after, limit)?;
+
+    let next_cursor = match events.last() {
+        Some(last) if events.len() as i64 == limit => Some(last.id),
+        _ => None,
+    };
+
+    Ok(EventPage {
+        events,
+        next_cursor,
+    })
+}
+
+/// Starts listening to the events of a game on behalf of one of its players. The events
+/// are the ones the relay gets from Postgres, whichever replica logged them. The stream
+/// ends once the player is out of the game.
+pub fn subscribe(game: i32, player: i32) -> UnboundedReceiver<EventView> {
+    let (sender, receiver) = unbounded();
+
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .entry(game)
+        .or_default()
+        .push(Subscriber { player, sender });
+
+    receiver
+}
+
+/// Delivers the events logged by every server replica to the players listening to this one,
+/// as Postgres announces them. The events logged while the listener was away are caught up
+/// with from the log. Only returns when the connection to the database is lost.
+pub fn relay(postgres_url: &str, last_relayed: &mut Option<i32>) -> color_eyre::Result<()> {
+    let mut listener = postgres::Client::connect(postgres_url, postgres::NoTls)?;
+    listener.batch_execute(&format!("LISTEN {}", CHANNEL))?;
+
+    // Events can be both in the log and announced, if they were logged in the meantime
+    let mut caught_up = HashSet::new();
+    let conn = db::connection()?;
+
+    match *last_relayed {
+        Some(after) => {
+            let missed: Vec<LoggedEvent> = game_event::table
+                .filter(game_event::id.gt(after))
+                .order(game_event::id.asc())
+                .load(&conn)?;
+
+            for event in missed {
+                caught_up.insert(event.id);
+                *last_relayed = Some(event.id);
+                publish(&conn, event)?;
+            }
+        }
+        None => {
+            let latest: Option<i32> = game_event::table
+                .select(diesel::dsl::max(game_event::id))
+                .first(&conn)?;
+            *last_relayed = Some(latest.unwrap_or(0));
+        }
+    }
+
+    drop(conn);
+
+    let mut notifications = listener.notifications();
+    let mut announced = notifications.blocking_iter();
+
+    while let Some(notification) = announced.next()? {
+        let id: i32 = notification.payload().parse()?;
+
+        if caught_up.remove(&id) {
+            continue;
+        }
+
+        let conn = db::connection()?;
+        let event: LoggedEvent = game_event::table.find(id).first(&conn)?;
+        *last_relayed = (*last_relayed).max(Some(id));
+        publish(&conn, event)?;
+    }
+
+    Ok(())
+}
+
+/// Hands the event over to the players of its game who are listening to this replica
+fn publish(conn: &PgConnection, event: LoggedEvent) -> QueryResult<()> {
+    if !SUBSCRIBERS.lock().unwrap().contains_key(&event.game) {
+        return Ok(());
+    }
+
+    // Every subscribe and publish waits on the lock, so it isn't held while querying
+    let involved: Vec<i32> = event.actor.into_iter().chain(event.subject).collect();
+    let codenames = codenames_of(conn, event.game, &involved)?;
+
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+
+    let listeners = match subscribers.get_mut(&event.game) {
+        Some(listeners) => listeners,
+        None => return Ok(()),
+    };
+
+    // Drop the listeners whose connection went away
+    listeners.retain(|listener| !listener.sender.is_closed());
+
+    if listeners.is_empty() {
+        subscribers.remove(&event.game);
+        return Ok(());
+    }
+
+    listeners
+        .iter()
+        .filter(|listener| match event.recipient {
+            Some(recipient) => recipient == listener.player,
+            None => true,
+        })
+        .for_each(|listener| {
+            let viewer = Viewer {
+                player: listener.player,
+                is_admin: false,
+            };
+            // Live events are about games which are still going on
+            let view = event.view(&viewer, false, &codenames);
+            let _ = listener.sender.unbounded_send(view);
+        });
+
+    // Whoever left, was kicked or got banned stops hearing about the game, which ends their stream
+    if event.kind == GameEventKind::PLAYER_LEFT {
+        listeners.retain(|listener| Some(listener.player) != event.actor);
+    }
+
+    Ok(())
+}
@@ -1,22 +1,40 @@
 use crate::db;
-use crate::models::enums::{GameStatus, PlayerStatus, TargetStatus};
+use crate::models::admin::like_pattern;
+use crate::models::assignment::{make_ring, Assignment, NewAssignment};
+use crate::models::constants;
+use crate::models::enums::{
+    GameStatus, KillClaimStatus, KillConfirmation, PlayerStatus, TargetStatus,
+};
+use crate::models::events::{self, GameEvent};
+use crate::models::kill_claim::{DisputedKill, KillClaim, NewKillClaim};
+use crate::models::leaderboard;
+use crate::models::membership::{self, Admission};
 use crate::models::model_errors::{ModelError, Result};
 use crate::models::player::{AgentStats, Player};
-use crate::models::constants;
+use crate::models::rating;
+use crate::models::settings::{GameRules, GameSettings};
 use crate::utils::genstring::{get_agent_name, get_game_code};
 
 use chrono::{DateTime, Utc};
-use color_eyre::Report;
+use color_eyre::{eyre::eyre, Report};
+use diesel::pg::data_types::PgInterval;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Integer, Nullable, Timestamptz, Varchar};
 use diesel::{
     result::DatabaseErrorKind::UniqueViolation, result::Error::DatabaseError, result::QueryResult,
     Associations, Identifiable, Insertable, Queryable,
 };
+use rand::{seq::SliceRandom, thread_rng};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tracing::{error, info};
 
 use crate::schema::*;
 
+sql_function!(fn pg_try_advisory_xact_lock(key: BigInt) -> Bool);
+
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
 #[table_name = "game"]
 pub struct NewGame {
@@ -40,6 +58,7 @@ pub struct Game {
     pub created_at: DateTime<Utc>,
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
+    pub winner: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Identifiable, Insertable)]
@@ -66,6 +85,7 @@ pub struct PlayerGame {
 
 #[derive(Debug, Serialize, Queryable)]
 pub struct GamePlayerInfo {
+    pub id: i32, // Lets the owner pick whom to kick, ban or hand the game to
     pub nickname: String,
     pub picture: Option<String>,
 }
@@ -77,12 +97,22 @@ pub struct GameInfo {
     pub players: Vec<GamePlayerInfo>,
     pub max_players: i32,
     pub has_started: bool,
-    pub start_time: Option<DateTime<Utc>>
+    pub is_paused: bool,
+    pub start_time: Option<DateTime<Utc>>,
+    pub remaining_seconds: Option<i64>,
+    pub settings: GameSettings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameStatusInfo {
+    pub game_status: GameStatus,
+    pub is_paused: bool,
+    pub remaining_seconds: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct GameStats {
-    pub winner: AgentStats,
+    pub winner: Option<AgentStats>,
     pub ranking: Vec<AgentStats>,
 }
 
@@ -91,10 +121,55 @@ pub struct Codenames {
     pub codenames: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Queryable)]
-pub struct PlayerCount {
-    assassin: i32,
-    count: usize,
+/// A public game waiting for players, which anybody can join
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct Lobby {
+    #[serde(skip)]
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Varchar"]
+    pub game_code: String,
+    #[sql_type = "Nullable<Varchar>"]
+    pub game_name: Option<String>,
+    #[sql_type = "Varchar"]
+    pub owner_nickname: String,
+    #[sql_type = "BigInt"]
+    pub players: i64,
+    #[sql_type = "Integer"]
+    pub max_players: i32,
+    #[sql_type = "Integer"]
+    pub duration_minutes: i32,
+    #[sql_type = "Timestamptz"]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LobbyPage {
+    pub lobbies: Vec<Lobby>,
+    pub next_cursor: Option<i32>, // Pass it as `after` to get the following page
+}
+
+/// An agent of a game, along with the place it got in the game
+#[derive(Debug, QueryableByName)]
+struct RankedAgentRow {
+    #[sql_type = "Integer"]
+    game: i32,
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Varchar"]
+    nickname: String,
+    #[sql_type = "Varchar"]
+    codename: String,
+    #[sql_type = "Nullable<Varchar>"]
+    picture: Option<String>,
+    #[sql_type = "BigInt"]
+    kills: i64,
+    #[sql_type = "BigInt"]
+    deaths: i64,
+    #[sql_type = "BigInt"]
+    score: i64,
+    #[sql_type = "BigInt"]
+    placement: i64,
 }
 
 impl Game {
@@ -112,7 +187,37 @@ impl Game {
         Ok(game)
     }
 
-    pub fn new(game_name: String, game_owner: i32, max_players: Option<i32>) -> Result<Self> {
+    /// Fetches the requested game, as long as the player is one of its members
+    pub fn find_as_member(code: &String, player_id: i32) -> Result<Self> {
+        let conn = db::connection()?;
+
+        let requested_game: Game = game::table
+            .filter(game::code.eq(code))
+            .first(&conn)
+            .map_err(|_| ModelError::GameNotFound)?;
+
+        let is_user_in_game = playergame::table
+            .filter(playergame::game.eq(requested_game.id))
+            .filter(playergame::player.eq(player_id))
+            .filter(playergame::status.ne(PlayerStatus::LEFT_GAME))
+            .count()
+            .get_result::<i64>(&conn)?
+            > 0;
+
+        if !is_user_in_game {
+            info!(
+                "User {} is currently not in the requested game {}",
+                player_id, code
+            );
+            return Err(ModelError::NotInGame);
+        }
+
+        Ok(requested_game)
+    }
+
+    pub fn new(game_name: String, game_owner: i32, settings: GameSettings) -> Result<Self> {
+        settings.validate()?;
+
         let conn = db::connection()?;
         let code = get_game_code();
 
@@ -120,23 +225,12 @@ impl Game {
             name: game_name.clone(),
             owner: game_owner,
             code,
-            max_players: max_players.unwrap_or(constants::DEFAULT_MAX_PLAYERS),
+            max_players: settings.max_players,
             status: GameStatus::WAITING_FOR_PLAYERS,
         };
 
         conn.transaction(|| {
-            let active_game_count = playergame::table
-                .inner_join(game::table)
-                .filter(playergame::player.eq(game_owner))
-                .filter(playergame::status.ne(PlayerStatus::LEFT_GAME))
-                .filter(game::status.ne(GameStatus::FINISHED))
-                .count()
-                .get_result::<i64>(&conn)?;
-
-            if active_game_count > 0 {
-                info!("User has active game: rolling back! {}", active_game_count);
-                return Err(ModelError::AlreadyInAnotherGame);
-            }
+            membership::check_can_create(&conn, game_owner)?;
 
             // Keep on generating codes until we get a unique one
             // Since the number of possible codes is around 2 trillion, we expect this
@@ -161,6 +255,16 @@ impl Game {
                             .values(new_player_game)
                             .execute(&conn)?;
 
+                        diesel::insert_into(game_settings::table)
+                            .values((game_settings::game.eq(game.id), &settings.rules))
+                            .execute(&conn)?;
+
+                        events::notify_game(
+                            &conn,
+                            game.id,
+                            GameEvent::GameCreated { owner: game_owner },
+                        )?;
+
                         return Ok(game);
                     },
 
@@ -180,27 +284,15 @@ impl Game {
         })
     }
 
-    pub fn join(code: &String, player_id: i32) -> Result<()> {
+    pub fn join(code: &str, player_id: i32) -> Result<()> {
         let conn = db::connection()?;
         let codename = get_agent_name();
 
         conn.transaction(|| {
-            let requested_game: Game = game::table
-                .filter(game::code.eq(code))
-                .first(&conn)
-                .map_err(|_| ModelError::GameNotFound)?;
-
-            //Find if player is already in a game, if so reject request
-            let active_game_count = playergame::table
-                .inner_join(game::table)
-                .filter(playergame::player.eq(player_id))
-                .filter(game::status.eq(GameStatus::ACTIVE))
-                .count()
-                .get_result::<i64>(&conn)?;
-
-            if active_game_count > 0 {
-                return Err(ModelError::AlreadyInAnotherGame);
-            }
+            let Admission {
+                game: requested_game,
+                is_late_join,
+            } = membership::admit(&conn, code, player_id)?;
 
             let new_player_game = NewPlayerGame {
                 player: player_id,
@@ -210,37 +302,209 @@ impl Game {
             };
 
             diesel::insert_into(playergame::table)
-                .values(new_player_game.clone())
-                .execute(&conn)
-                .map_err(|_| ModelError::AlreadyInRequestedGame)?;
+                .values(new_player_game)
+                .execute(&conn)?;
+
+            events::notify_game(
+                &conn,
+                requested_game.id,
+                GameEvent::PlayerJoined { player: player_id },
+            )?;
+
+            if is_late_join {
+                requested_game.splice_into_ring(&conn, player_id)?;
+            }
 
             Ok(())
         })
     }
 
-    pub fn start_game(code: &String, player_id: i32) -> Result<()> {
+    /// Public games waiting for players which still have free slots, oldest first. They can be
+    /// searched by name and by maximum number of players. Games the player is banned from are
+    /// left out.
+    pub fn lobbies(
+        player_id: i32,
+        search: Option<&str>,
+        min_size: Option<i32>,
+        max_size: Option<i32>,
+        after: i32,
+        limit: i64,
+    ) -> Result<LobbyPage> {
+        if limit <= 0 || limit > constants::MAX_PAGE_SIZE {
+            return Err(ModelError::InvalidPagination);
+        }
+
         let conn = db::connection()?;
-        diesel::update(
-            game::table
-                .filter(game::code.eq(code))
-                .filter(game::owner.eq(player_id)),
+
+        let lobbies: Vec<Lobby> = diesel::sql_query(
+            "SELECT g.id, g.code AS game_code, g.name AS game_name,
+                p.nickname AS owner_nickname, COUNT(pg.player) AS players,
+                g.max_players, s.duration_minutes, g.created_at
+            FROM game g
+            INNER JOIN game_settings s ON s.game = g.id
+            INNER JOIN player p ON p.id = g.owner
+            LEFT JOIN playergame pg ON pg.game = g.id AND pg.status <> 'LEFT_GAME'
+            WHERE g.status = 'WAITING_FOR_PLAYERS' AND s.is_public AND g.id > $1
+                AND ($2 IS NULL OR g.name ILIKE $2)
+                AND ($3 IS NULL OR g.max_players >= $3)
+                AND ($4 IS NULL OR g.max_players <= $4)
+                AND NOT EXISTS (
+                    SELECT 1 FROM game_ban b WHERE b.game = g.id AND b.player = $5
+                )
+            GROUP BY g.id, s.game, p.id
+            HAVING COUNT(pg.player) < g.max_players
+            ORDER BY g.id
+            LIMIT $6",
         )
-        .set((
-            game::status.eq(GameStatus::ACTIVE),
-            game::start_time.eq(chrono::offset::Utc::now()),
-            game::end_time.eq(chrono::offset::Utc::now() + chrono::Duration::days(3)), //TODO: de-hardcode this
-        ))
-        .execute(&conn)?;
+        .bind::<Integer, _>(after)
+        .bind::<Nullable<Varchar>, _>(search.map(like_pattern))
+        .bind::<Nullable<Integer>, _>(min_size)
+        .bind::<Nullable<Integer>, _>(max_size)
+        .bind::<Integer, _>(player_id)
+        .bind::<BigInt, _>(limit)
+        .load(&conn)?;
+
+        let next_cursor = match lobbies.last() {
+            Some(last) if lobbies.len() as i64 == limit => Some(last.id),
+            _ => None,
+        };
+
+        Ok(LobbyPage {
+            lobbies,
+            next_cursor,
+        })
+    }
+
+    /// Inserts a late joiner into the ring, between a random assassin and their target
+    fn splice_into_ring(&self, conn: &PgConnection, player_id: i32) -> Result<()> {
+        let current: Vec<Assignment> = assignment::table
+            .filter(assignment::game.eq(self.id))
+            .filter(assignment::status.eq(TargetStatus::CURRENT))
+            .for_update()
+            .load(conn)?;
+
+        let split = current.choose(&mut thread_rng()).ok_or_else(|| {
+            ModelError::UnknownError(eyre!("Game {} has no current assignments", self.code))
+        })?;
+
+        split.close(conn, TargetStatus::REASSIGNED)?;
+
+        diesel::insert_into(assignment::table)
+            .values(vec![
+                NewAssignment {
+                    assassin: split.assassin,
+                    target: player_id,
+                    game: self.id,
+                    status: TargetStatus::CURRENT,
+                },
+                NewAssignment {
+                    assassin: player_id,
+                    target: split.target,
+                    game: self.id,
+                    status: TargetStatus::CURRENT,
+                },
+            ])
+            .execute(conn)?;
+
+        events::notify_player(
+            conn,
+            self.id,
+            split.assassin,
+            GameEvent::TargetChanged {
+                assassin: split.assassin,
+                target: player_id,
+            },
+        )?;
+        events::notify_player(
+            conn,
+            self.id,
+            player_id,
+            GameEvent::TargetChanged {
+                assassin: player_id,
+                target: split.target,
+            },
+        )?;
 
         Ok(())
     }
 
+    pub fn start_game(code: &String, player_id: i32) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            // Lock the game row, so that nobody can join while we're building the ring
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .for_update()
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
+
+            if requested_game.owner != player_id {
+                info!(
+                    "User {} is not the owner of game {}. Cannot start game",
+                    player_id, code
+                );
+                return Err(ModelError::NotGameOwner);
+            }
+
+            if requested_game.status != GameStatus::WAITING_FOR_PLAYERS {
+                info!("Game {} has already started. Cannot start it again", code);
+                return Err(ModelError::GameAlreadyStarted);
+            }
+
+            let rules = GameRules::of(&conn, requested_game.id)?;
+
+            let mut agents: Vec<i32> = playergame::table
+                .filter(playergame::game.eq(requested_game.id))
+                .filter(playergame::status.eq(PlayerStatus::ALIVE))
+                .select(playergame::player)
+                .load(&conn)?;
+
+            if agents.len() < 2 || agents.len() < rules.min_players as usize {
+                info!(
+                    "Game {} has only {} players. Cannot start game",
+                    code,
+                    agents.len()
+                );
+                return Err(ModelError::NotEnoughPlayers);
+            }
+
+            // Every agent hunts the next one in a random permutation, so that
+            // the assignments form a single cycle through all the players
+            agents.shuffle(&mut thread_rng());
+
+            diesel::insert_into(assignment::table)
+                .values(make_ring(requested_game.id, &agents))
+                .execute(&conn)?;
+
+            let now = chrono::offset::Utc::now();
+
+            diesel::update(&requested_game)
+                .set((
+                    game::status.eq(GameStatus::ACTIVE),
+                    game::start_time.eq(now),
+                    game::end_time
+                        .eq(now + chrono::Duration::minutes(rules.duration_minutes.into())),
+                ))
+                .execute(&conn)?;
+
+            events::notify_game(
+                &conn,
+                requested_game.id,
+                GameEvent::GameStarted { by: player_id },
+            )?;
+
+            Ok(())
+        })
+    }
+
     pub fn stop_game(code: &String, player_id: i32) -> Result<()> {
         let conn = db::connection()?;
 
         conn.transaction(|| {
             let requested_game: Game = game::table
                 .filter(game::code.eq(code))
+                .for_update()
                 .first(&conn)
                 .map_err(|_| ModelError::GameNotFound)?;
 
@@ -255,11 +519,14 @@ impl Game {
                 return Err(ModelError::NotInGame);
             }
 
-            if requested_game.end_time.is_some() && requested_game.end_time.unwrap() > chrono::offset::Utc::now() {
-                diesel::update(&requested_game)
-                    .set(game::status.eq(GameStatus::FINISHED))
-                    .execute(&conn)?;
-                Ok(())
+            if requested_game.status == GameStatus::FINISHED {
+                info!("Game {} is already finished", code);
+                return Err(ModelError::GameNotActive);
+            }
+
+            // The clock of paused games is frozen, so their end time may have passed already
+            if requested_game.remaining_time(&conn)? == Some(chrono::Duration::zero()) {
+                requested_game.finish_with_standings(&conn)
             } else {
                 info!("Couldn't stop game (Either end time hasn't been set yet or the end time hasn't arrived yet)");
                 Err(ModelError::GameNotStarted)
@@ -267,14 +534,209 @@ impl Game {
         })
     }
 
-    pub fn get_game_status(code: &String) -> Result<GameStatus> {
+    pub fn update_settings(code: &String, player_id: i32, settings: GameSettings) -> Result<()> {
+        settings.validate()?;
+
         let conn = db::connection()?;
-        let requested_game: Game = game::table
-            .filter(game::code.eq(code))
-            .first(&conn)
-            .map_err(|_| ModelError::GameNotFound)?;
 
-        Ok(requested_game.status)
+        conn.transaction(|| {
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .for_update()
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
+
+            if requested_game.owner != player_id {
+                info!(
+                    "User {} is not the owner of game {}. Cannot update settings",
+                    player_id, code
+                );
+                return Err(ModelError::NotGameOwner);
+            }
+
+            if requested_game.status != GameStatus::WAITING_FOR_PLAYERS {
+                info!("Game {} has already started. Cannot update settings", code);
+                return Err(ModelError::GameAlreadyStarted);
+            }
+
+            let player_count = membership::member_count(&conn, requested_game.id)?;
+
+            if player_count > i64::from(settings.max_players) {
+                info!(
+                    "Game {} already has {} players. Cannot lower the maximum to {}",
+                    code, player_count, settings.max_players
+                );
+                return Err(ModelError::InvalidSettings);
+            }
+
+            diesel::update(&requested_game)
+                .set(game::max_players.eq(settings.max_players))
+                .execute(&conn)?;
+
+            diesel::update(game_settings::table.find(requested_game.id))
+                .set(&settings.rules)
+                .execute(&conn)?;
+
+            Ok(())
+        })
+    }
+
+    pub fn get_game_status(code: &String) -> Result<GameStatusInfo> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
+
+            let remaining_time = requested_game.remaining_time(&conn)?;
+
+            Ok(GameStatusInfo {
+                is_paused: requested_game.status == GameStatus::PAUSED,
+                game_status: requested_game.status,
+                remaining_seconds: remaining_time.map(|t| t.num_seconds()),
+            })
+        })
+    }
+
+    pub fn pause_game(code: &String, player_id: i32) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+
+            if requested_game.owner != player_id {
+                info!(
+                    "User {} is not the owner of game {}. Cannot pause game",
+                    player_id, code
+                );
+                return Err(ModelError::NotGameOwner);
+            }
+
+            requested_game.pause(&conn, player_id)
+        })
+    }
+
+    /// Stops the clock of the game, on behalf of the given player
+    pub(crate) fn pause(&self, conn: &PgConnection, by: i32) -> Result<()> {
+        self.ensure_active()?;
+
+        diesel::insert_into(game_pause::table)
+            .values(game_pause::game.eq(self.id))
+            .execute(conn)?;
+
+        diesel::update(self)
+            .set(game::status.eq(GameStatus::PAUSED))
+            .execute(conn)?;
+
+        events::notify_game(conn, self.id, GameEvent::GamePaused { by })?;
+
+        Ok(())
+    }
+
+    pub fn resume_game(code: &String, player_id: i32) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .for_update()
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
+
+            if requested_game.owner != player_id {
+                info!(
+                    "User {} is not the owner of game {}. Cannot resume game",
+                    player_id, code
+                );
+                return Err(ModelError::NotGameOwner);
+            }
+
+            requested_game.resume(&conn, player_id)
+        })
+    }
+
+    /// Starts the clock of a paused game again, on behalf of the given player
+    pub(crate) fn resume(&self, conn: &PgConnection, by: i32) -> Result<()> {
+        if self.status != GameStatus::PAUSED {
+            info!("Game {} is not paused. Cannot resume game", self.code);
+            return Err(ModelError::GameNotPaused);
+        }
+
+        let now = chrono::offset::Utc::now();
+
+        let paused_since: DateTime<Utc> = diesel::update(
+            game_pause::table
+                .filter(game_pause::game.eq(self.id))
+                .filter(game_pause::end_time.is_null()),
+        )
+        .set(game_pause::end_time.eq(now))
+        .returning(game_pause::start_time)
+        .get_result(conn)?;
+
+        // The time spent paused doesn't count towards the game duration
+        let end_time = self.end_time.map(|t| t + (now - paused_since));
+
+        // Nor towards the time the victims have to answer the kill claims, as they couldn't
+        let paused_for = PgInterval::from_microseconds(
+            (now - paused_since).num_microseconds().unwrap_or(i64::MAX),
+        );
+
+        diesel::update(
+            kill_claim::table
+                .filter(kill_claim::game.eq(self.id))
+                .filter(kill_claim::status.eq(KillClaimStatus::PENDING)),
+        )
+        .set(kill_claim::created_at.eq(kill_claim::created_at + paused_for))
+        .execute(conn)?;
+
+        diesel::update(self)
+            .set((
+                game::status.eq(GameStatus::ACTIVE),
+                game::end_time.eq(end_time),
+            ))
+            .execute(conn)?;
+
+        events::notify_game(conn, self.id, GameEvent::GameResumed { by })?;
+
+        Ok(())
+    }
+
+    /// Time left before the game ends. While the game is paused, the clock is frozen.
+    fn remaining_time(&self, conn: &PgConnection) -> QueryResult<Option<chrono::Duration>> {
+        let end_time = match (&self.status, self.end_time) {
+            (GameStatus::ACTIVE, Some(end_time)) | (GameStatus::PAUSED, Some(end_time)) => end_time,
+            _ => return Ok(None),
+        };
+
+        let now = match self.status {
+            GameStatus::PAUSED => game_pause::table
+                .filter(game_pause::game.eq(self.id))
+                .filter(game_pause::end_time.is_null())
+                .select(game_pause::start_time)
+                .first(conn)?,
+            _ => chrono::offset::Utc::now(),
+        };
+
+        Ok(Some(std::cmp::max(
+            end_time - now,
+            chrono::Duration::zero(),
+        )))
+    }
+
+    fn ensure_active(&self) -> Result<()> {
+        match self.status {
+            GameStatus::ACTIVE => Ok(()),
+            GameStatus::PAUSED => {
+                info!("Game {} is paused", self.code);
+                Err(ModelError::GamePaused)
+            }
+            _ => {
+                info!("Game {} is not active", self.code);
+                Err(ModelError::GameNotActive)
+            }
+        }
     }
 
     pub fn leave_game(code: &String, player_id: i32) -> Result<()> {
@@ -286,19 +748,253 @@ impl Game {
             let requested_game: Game = game::table
                 .filter(game::code.eq(code))
                 .filter(game::status.ne(GameStatus::FINISHED))
+                .for_update()
                 .first(&conn)
                 .map_err(|_| ModelError::GameNotFound)?;
 
-            // Update and leave the game
-            diesel::update(
-                playergame::table
-                    .filter(playergame::game.eq(requested_game.id))
-                    .filter(playergame::player.eq(player_id))
-                    .filter(playergame::status.ne(PlayerStatus::LEFT_GAME)),
-            )
-            .set(playergame::status.eq(PlayerStatus::LEFT_GAME))
-            .execute(&conn)?;
+            requested_game.remove_player(&conn, player_id)
+        })
+    }
+
+    /// Takes the player out of the game, whether they left or were kicked out of it
+    pub(crate) fn remove_player(&self, conn: &PgConnection, player_id: i32) -> Result<()> {
+        let left = diesel::update(
+            playergame::table
+                .filter(playergame::game.eq(self.id))
+                .filter(playergame::player.eq(player_id))
+                .filter(playergame::status.ne(PlayerStatus::LEFT_GAME)),
+        )
+        .set(playergame::status.eq(PlayerStatus::LEFT_GAME))
+        .execute(conn)?;
+
+        if left == 0 {
+            info!(
+                "User {} is currently not in the requested game {}. Cannot leave game",
+                player_id, self.code
+            );
+            return Err(ModelError::NotInGame);
+        }
+
+        events::notify_game(conn, self.id, GameEvent::PlayerLeft { player: player_id })?;
+
+        self.reject_open_claims_of(conn, player_id)?;
+
+        if let GameStatus::ACTIVE | GameStatus::PAUSED = self.status {
+            self.reassign_target(conn, player_id)?;
+        }
+
+        // Losing an agent may have ended the game, otherwise someone has to run it
+        let status: GameStatus = game::table.find(self.id).select(game::status).first(conn)?;
+
+        if status != GameStatus::FINISHED && self.owner == player_id {
+            self.pass_ownership(conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Hands the target of a player who left the game to whoever was hunting them
+    fn reassign_target(&self, conn: &PgConnection, leaver: i32) -> Result<()> {
+        // Dead players are not part of the ring anymore
+        let hunter = match Assignment::current_on(conn, self.id, leaver)? {
+            Some(hunter) => hunter,
+            None => return Ok(()),
+        };
+
+        let own = Assignment::current_of(conn, self.id, leaver)?.ok_or_else(|| {
+            ModelError::UnknownError(eyre!(
+                "Player {} has no current target in game {}",
+                leaver,
+                self.code
+            ))
+        })?;
+
+        hunter.close(conn, TargetStatus::TARGET_LEFT)?;
+        own.close(conn, TargetStatus::REASSIGNED)?;
+
+        if own.target == hunter.assassin {
+            info!(
+                "User {} is the last agent left in game {}",
+                hunter.assassin, self.code
+            );
+            self.finish(conn, Some(hunter.assassin))?;
+        } else {
+            diesel::insert_into(assignment::table)
+                .values(NewAssignment {
+                    assassin: hunter.assassin,
+                    target: own.target,
+                    game: self.id,
+                    status: TargetStatus::CURRENT,
+                })
+                .execute(conn)?;
+
+            events::notify_player(
+                conn,
+                self.id,
+                hunter.assassin,
+                GameEvent::TargetChanged {
+                    assassin: hunter.assassin,
+                    target: own.target,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Passes the ownership of the game to the member who joined the earliest.
+    /// If nobody is left in the game, it is closed.
+    fn pass_ownership(&self, conn: &PgConnection) -> Result<()> {
+        let new_owner: Option<i32> = playergame::table
+            .filter(playergame::game.eq(self.id))
+            .filter(playergame::status.ne(PlayerStatus::LEFT_GAME))
+            .order(playergame::joined_at.asc())
+            .select(playergame::player)
+            .first(conn)
+            .optional()?;
+
+        match new_owner {
+            Some(new_owner) => {
+                info!("Passing ownership of game {} to user {}", self.code, new_owner);
+                diesel::update(self)
+                    .set(game::owner.eq(new_owner))
+                    .execute(conn)?;
+
+                events::notify_game(
+                    conn,
+                    self.id,
+                    GameEvent::OwnerChanged {
+                        from: self.owner,
+                        to: new_owner,
+                    },
+                )?;
+            }
+            None => {
+                info!("Game {} is empty: closing it", self.code);
+                self.finish(conn, None)?;
+            }
+        }
 
+        Ok(())
+    }
+
+    /// Fetches and locks the requested game, as long as the player owns it and it's not over
+    fn lock_as_owner(conn: &PgConnection, code: &String, player_id: i32) -> Result<Game> {
+        let requested_game = Game::lock_and_settle(conn, code)?;
+
+        if requested_game.owner != player_id {
+            info!("User {} is not the owner of game {}", player_id, code);
+            return Err(ModelError::NotGameOwner);
+        }
+
+        if requested_game.status == GameStatus::FINISHED {
+            info!("Game {} is already finished", code);
+            return Err(ModelError::GameNotActive);
+        }
+
+        Ok(requested_game)
+    }
+
+    /// Throws a player out of the game. If the game is going on, their target is
+    /// handed to whoever was hunting them.
+    pub fn kick_player(code: &String, owner_id: i32, player_id: i32) -> Result<()> {
+        if owner_id == player_id {
+            return Err(ModelError::CannotModerateSelf);
+        }
+
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_as_owner(&conn, code, owner_id)?;
+            requested_game.remove_player(&conn, player_id)?;
+
+            info!(
+                "User {} was kicked out of game {} by its owner",
+                player_id, code
+            );
+            Ok(())
+        })
+    }
+
+    /// Keeps a player from ever joining the game again, kicking them out of it if needed
+    pub fn ban_player(code: &String, owner_id: i32, player_id: i32) -> Result<()> {
+        if owner_id == player_id {
+            return Err(ModelError::CannotModerateSelf);
+        }
+
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_as_owner(&conn, code, owner_id)?;
+
+            let banned: Player = player::table
+                .find(player_id)
+                .first(&conn)
+                .map_err(|_| ModelError::PlayerNotFound)?;
+
+            diesel::insert_into(game_ban::table)
+                .values((
+                    game_ban::game.eq(requested_game.id),
+                    game_ban::player.eq(banned.id),
+                    game_ban::banned_by.eq(owner_id),
+                ))
+                .on_conflict_do_nothing()
+                .execute(&conn)?;
+
+            match requested_game.remove_player(&conn, banned.id) {
+                Ok(()) | Err(ModelError::NotInGame) => (),
+                Err(e) => return Err(e),
+            }
+
+            info!("User {} was banned from game {}", banned.uid, code);
+            Ok(())
+        })
+    }
+
+    /// Hands the game over to another of its members
+    pub fn transfer_ownership(code: &String, owner_id: i32, new_owner: i32) -> Result<()> {
+        if owner_id == new_owner {
+            return Err(ModelError::CannotModerateSelf);
+        }
+
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_as_owner(&conn, code, owner_id)?;
+
+            let is_member = playergame::table
+                .filter(playergame::game.eq(requested_game.id))
+                .filter(playergame::player.eq(new_owner))
+                .filter(playergame::status.ne(PlayerStatus::LEFT_GAME))
+                .count()
+                .get_result::<i64>(&conn)?
+                > 0;
+
+            if !is_member {
+                info!(
+                    "User {} is not in game {}. Cannot make them its owner",
+                    new_owner, code
+                );
+                return Err(ModelError::NotInGame);
+            }
+
+            diesel::update(&requested_game)
+                .set(game::owner.eq(new_owner))
+                .execute(&conn)?;
+
+            events::notify_game(
+                &conn,
+                requested_game.id,
+                GameEvent::OwnerChanged {
+                    from: owner_id,
+                    to: new_owner,
+                },
+            )?;
+
+            info!(
+                "Ownership of game {} passed from user {} to user {}",
+                code, owner_id, new_owner
+            );
             Ok(())
         })
     }
@@ -334,15 +1030,26 @@ impl Game {
             let players: Vec<GamePlayerInfo> = playergame::table
                 .inner_join(player::table)
                 .filter(playergame::game.eq(requested_game.id))
-                .select((player::nickname, player::picture))
+                .select((player::id, player::nickname, player::picture))
                 .load::<GamePlayerInfo>(&conn)?;
 
+            let remaining_time = requested_game.remaining_time(&conn)?;
+
+            let settings = GameSettings {
+                max_players: requested_game.max_players,
+                rules: GameRules::of(&conn, requested_game.id)?,
+            };
+
             let game_info = GameInfo {
                 //TODO: right now the game name is nullable, debate whether we should require it?
                 game_name: requested_game.name.unwrap(),
                 max_players: requested_game.max_players,
-                has_started: requested_game.status == GameStatus::ACTIVE,
+                has_started: requested_game.status == GameStatus::ACTIVE
+                    || requested_game.status == GameStatus::PAUSED,
+                is_paused: requested_game.status == GameStatus::PAUSED,
                 start_time: requested_game.start_time,
+                remaining_seconds: remaining_time.map(|t| t.num_seconds()),
+                settings,
                 admin_nickname: owner.nickname,
                 players,
             };
@@ -429,12 +1136,8 @@ impl Game {
         let conn = db::connection()?;
 
         conn.transaction(|| {
-            let requested_game: Game = game::table
-                .filter(game::code.eq(code))
-                .first(&conn)
-                .map_err(|_| ModelError::GameNotFound)?;
+            let requested_game = Game::lock_and_settle(&conn, code)?;
 
-            //TODO: should we check whether the game is active or not?
             let is_user_in_game = playergame::table
                 .filter(playergame::game.eq(requested_game.id))
                 .filter(playergame::player.eq(player_id))
@@ -450,77 +1153,598 @@ impl Game {
                 return Err(ModelError::NotInGame);
             }
 
-            let has_target = assignment::table
-                .filter(assignment::game.eq(requested_game.id))
-                .filter(assignment::assassin.eq(player_id))
-                .filter(assignment::status.eq(TargetStatus::CURRENT))
+            requested_game.ensure_active()?;
+
+            let kill = match Assignment::current_of(&conn, requested_game.id, player_id)? {
+                Some(kill) => kill,
+                None => {
+                    info!("User {} is has no target {}. Cannot kill", player_id, code);
+                    return Err(ModelError::NoCurrentTarget);
+                }
+            };
+
+            let rules = GameRules::of(&conn, requested_game.id)?;
+
+            if rules.kill_confirmation == KillConfirmation::ASSASSIN {
+                return requested_game.execute_kill(&conn, kill);
+            }
+
+            if KillClaim::open_on(&conn, requested_game.id, kill.target)?.is_some() {
+                info!("User {} has already claimed a kill in game {}", player_id, code);
+                return Err(ModelError::KillAlreadyClaimed);
+            }
+
+            // The kill only goes through once the victim confirms it
+            diesel::insert_into(kill_claim::table)
+                .values(NewKillClaim {
+                    game: requested_game.id,
+                    assassin: player_id,
+                    target: kill.target,
+                })
+                .execute(&conn)?;
+
+            events::notify_player(
+                &conn,
+                requested_game.id,
+                kill.target,
+                GameEvent::KillClaimed {
+                    assassin: player_id,
+                    target: kill.target,
+                },
+            )?;
+
+            Ok(())
+        })
+    }
+
+    pub fn confirm_kill(code: &String, player_id: i32) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+
+            requested_game.ensure_active()?;
+
+            let claim = KillClaim::open_on(&conn, requested_game.id, player_id)?
+                .ok_or(ModelError::NoPendingKillClaim)?;
+
+            requested_game.resolve_claim(&conn, &claim, true)
+        })
+    }
+
+    pub fn dispute_kill(code: &String, player_id: i32) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+
+            requested_game.ensure_active()?;
+
+            let claim = KillClaim::open_on(&conn, requested_game.id, player_id)?
+                .ok_or(ModelError::NoPendingKillClaim)?;
+
+            // Disputed claims don't time out: they wait for the game owner
+            claim.set_status(&conn, KillClaimStatus::DISPUTED)?;
+
+            events::notify_player(
+                &conn,
+                requested_game.id,
+                requested_game.owner,
+                GameEvent::KillDisputed {
+                    assassin: claim.assassin,
+                    target: claim.target,
+                },
+            )?;
+
+            Ok(())
+        })
+    }
+
+    pub fn get_disputed_kills(code: &String, player_id: i32) -> Result<Vec<DisputedKill>> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
+
+            if requested_game.owner != player_id {
+                info!(
+                    "User {} is not the owner of game {}. Cannot get disputed kills",
+                    player_id, code
+                );
+                return Err(ModelError::NotGameOwner);
+            }
+
+            requested_game.disputed_kills(&conn)
+        })
+    }
+
+    /// Kill claims of the game waiting for someone to settle them, oldest first
+    pub(crate) fn disputed_kills(&self, conn: &PgConnection) -> Result<Vec<DisputedKill>> {
+        let claims: Vec<KillClaim> = kill_claim::table
+            .filter(kill_claim::game.eq(self.id))
+            .filter(kill_claim::status.eq(KillClaimStatus::DISPUTED))
+            .order(kill_claim::created_at.asc())
+            .load(conn)?;
+
+        let nicknames: HashMap<i32, String> = player::table
+            .inner_join(playergame::table)
+            .filter(playergame::game.eq(self.id))
+            .select((player::id, player::nickname))
+            .load::<(i32, String)>(conn)?
+            .into_iter()
+            .collect();
+
+        let nickname_of = |player_id: i32| {
+            nicknames.get(&player_id).cloned().ok_or_else(|| {
+                ModelError::UnknownError(eyre!(
+                    "User {} has no nickname in game {}",
+                    player_id,
+                    self.code
+                ))
+            })
+        };
+
+        claims
+            .into_iter()
+            .map(|claim| {
+                Ok(DisputedKill {
+                    claim_id: claim.id,
+                    assassin_nickname: nickname_of(claim.assassin)?,
+                    target_nickname: nickname_of(claim.target)?,
+                    claimed_at: claim.created_at,
+                })
+            })
+            .collect()
+    }
+
+    pub fn resolve_disputed_kill(
+        code: &String,
+        player_id: i32,
+        claim_id: i32,
+        confirm: bool,
+    ) -> Result<()> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game = Game::lock_and_settle(&conn, code)?;
+
+            if requested_game.owner != player_id {
+                info!(
+                    "User {} is not the owner of game {}. Cannot resolve disputed kill",
+                    player_id, code
+                );
+                return Err(ModelError::NotGameOwner);
+            }
+
+            requested_game.resolve_dispute(&conn, claim_id, confirm)
+        })
+    }
+
+    /// Settles one of the disputed kill claims of the game
+    pub(crate) fn resolve_dispute(
+        &self,
+        conn: &PgConnection,
+        claim_id: i32,
+        confirm: bool,
+    ) -> Result<()> {
+        self.ensure_active()?;
+
+        let claim: KillClaim = kill_claim::table
+            .filter(kill_claim::id.eq(claim_id))
+            .filter(kill_claim::game.eq(self.id))
+            .filter(kill_claim::status.eq(KillClaimStatus::DISPUTED))
+            .for_update()
+            .first(conn)
+            .map_err(|_| ModelError::KillClaimNotFound)?;
+
+        self.resolve_claim(conn, &claim, confirm)
+    }
+
+    /// Fetches and locks the requested game, confirming the kill claims which have timed out.
+    pub(crate) fn lock_and_settle(conn: &PgConnection, code: &String) -> Result<Game> {
+        let requested_game: Game = game::table
+            .filter(game::code.eq(code))
+            .for_update()
+            .first(conn)
+            .map_err(|_| ModelError::GameNotFound)?;
+
+        if requested_game.status != GameStatus::ACTIVE {
+            return Ok(requested_game);
+        }
+
+        let expired_claims = KillClaim::expired(conn, requested_game.id)?;
+
+        if expired_claims.is_empty() {
+            return Ok(requested_game);
+        }
+
+        for claim in expired_claims.iter() {
+            info!("Kill claim {} timed out: confirming it", claim.id);
+            requested_game.resolve_claim(conn, claim, true)?;
+        }
+
+        // Confirming the claims may have finished the game
+        Ok(game::table.find(requested_game.id).first(conn)?)
+    }
+
+    fn resolve_claim(&self, conn: &PgConnection, claim: &KillClaim, confirm: bool) -> Result<()> {
+        if !confirm {
+            return self.reject_claim(conn, claim);
+        }
+
+        // The assassin may have lost the target in the meantime (e.g. because the game ended)
+        match Assignment::current_of(conn, self.id, claim.assassin)? {
+            Some(kill) if kill.target == claim.target => {
+                claim.set_status(conn, KillClaimStatus::CONFIRMED)?;
+                self.execute_kill(conn, kill)
+            }
+            _ => {
+                info!("Kill claim {} is stale: rejecting it", claim.id);
+                claim.set_status(conn, KillClaimStatus::REJECTED)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn reject_claim(&self, conn: &PgConnection, claim: &KillClaim) -> Result<()> {
+        claim.set_status(conn, KillClaimStatus::REJECTED)?;
+        events::notify_player(
+            conn,
+            self.id,
+            claim.assassin,
+            GameEvent::KillRejected {
+                assassin: claim.assassin,
+                target: claim.target,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Rejects the claims a player made before dying or leaving the game, as their target
+    /// is handed over to someone else
+    fn reject_open_claims_of(&self, conn: &PgConnection, assassin: i32) -> Result<()> {
+        for claim in KillClaim::open_by(conn, self.id, assassin)?.iter() {
+            info!(
+                "User {} is out of game {}: rejecting kill claim {}",
+                assassin, self.code, claim.id
+            );
+            self.reject_claim(conn, claim)?;
+        }
+        Ok(())
+    }
+
+    fn execute_kill(&self, conn: &PgConnection, kill: Assignment) -> Result<()> {
+        kill.close(conn, TargetStatus::KILL_SUCCESS)?;
+
+        diesel::update(
+            playergame::table
+                .filter(playergame::game.eq(self.id))
+                .filter(playergame::player.eq(kill.target)),
+        )
+        .set(playergame::status.eq(PlayerStatus::DEAD))
+        .execute(conn)?;
+
+        self.reject_open_claims_of(conn, kill.target)?;
+
+        events::notify_game(
+            conn,
+            self.id,
+            GameEvent::Kill {
+                assassin: kill.assassin,
+                target: kill.target,
+            },
+        )?;
+
+        // The victim's target is passed on to the killer
+        let inherited = Assignment::current_of(conn, self.id, kill.target)?.ok_or_else(|| {
+            ModelError::UnknownError(eyre!(
+                "Victim {} has no current target in game {}",
+                kill.target,
+                self.code
+            ))
+        })?;
+
+        inherited.close(conn, TargetStatus::REASSIGNED)?;
+
+        if inherited.target == kill.assassin {
+            // The victim was hunting the killer: they were the last two agents alive
+            info!(
+                "User {} is the last agent alive in game {}",
+                kill.assassin, self.code
+            );
+            self.finish(conn, Some(kill.assassin))?;
+        } else {
+            diesel::insert_into(assignment::table)
+                .values(NewAssignment {
+                    assassin: kill.assassin,
+                    target: inherited.target,
+                    game: self.id,
+                    status: TargetStatus::CURRENT,
+                })
+                .execute(conn)?;
+
+            events::notify_player(
+                conn,
+                self.id,
+                kill.assassin,
+                GameEvent::TargetChanged {
+                    assassin: kill.assassin,
+                    target: inherited.target,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Marks the game as finished, closing all the assignments which are still open
+    pub(crate) fn finish(&self, conn: &PgConnection, winner: Option<i32>) -> Result<()> {
+        let now = chrono::offset::Utc::now();
+
+        diesel::update(
+            assignment::table
+                .filter(assignment::game.eq(self.id))
+                .filter(assignment::status.eq(TargetStatus::CURRENT)),
+        )
+        .set((
+            assignment::status.eq(TargetStatus::GAME_END),
+            assignment::end_time.eq(now),
+        ))
+        .execute(conn)?;
+
+        diesel::update(
+            kill_claim::table
+                .filter(kill_claim::game.eq(self.id))
+                .filter(
+                    kill_claim::status
+                        .eq(KillClaimStatus::PENDING)
+                        .or(kill_claim::status.eq(KillClaimStatus::DISPUTED)),
+                ),
+        )
+        .set((
+            kill_claim::status.eq(KillClaimStatus::REJECTED),
+            kill_claim::resolved_at.eq(now),
+        ))
+        .execute(conn)?;
+
+        diesel::update(
+            game_pause::table
+                .filter(game_pause::game.eq(self.id))
+                .filter(game_pause::end_time.is_null()),
+        )
+        .set(game_pause::end_time.eq(now))
+        .execute(conn)?;
+
+        diesel::update(self)
+            .set((
+                game::status.eq(GameStatus::FINISHED),
+                game::end_time.eq(now),
+                game::winner.eq(winner),
+            ))
+            .execute(conn)?;
+
+        // Games which never started neither affect the ratings nor get a place in the rankings
+        if self.status != GameStatus::WAITING_FOR_PLAYERS {
+            // The winner was recorded above, so it gets its points
+            let standings = self.standings(conn)?;
+            let players: Vec<i32> = standings.iter().map(|(id, _)| *id).collect();
+            let kills = self.kills(conn)?;
+
+            let ratings = rating::rate_game(conn, self.id, &players, &kills)?;
+            leaderboard::record_game(conn, &standings, &kills, &ratings, winner, now)?;
+        }
+
+        events::notify_game(conn, self.id, GameEvent::GameEnded { winner })?;
+
+        Ok(())
+    }
+
+    pub fn game_stats(code: &String, player_id: i32) -> Result<GameStats> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
+
+            let is_user_in_game = playergame::table
+                .filter(playergame::game.eq(requested_game.id))
+                .filter(playergame::player.eq(player_id))
                 .count()
                 .get_result::<i64>(&conn)?
                 > 0;
 
-            if !has_target {
-                info!("User {} is has no target {}. Cannot kill", player_id, code);
-                return Err(ModelError::NoCurrentTarget);
+            if !is_user_in_game {
+                info!(
+                    "User {} is currently not in the requested game {}. Cannot get game stats",
+                    player_id, code
+                );
+                return Err(ModelError::NotInGame);
             }
 
-            diesel::update(
-                assignment::table
-                    .filter(assignment::game.eq(requested_game.id))
-                    .filter(assignment::assassin.eq(player_id))
-                    .filter(assignment::status.eq(TargetStatus::CURRENT)),
-            )
-            .set(assignment::status.eq(TargetStatus::KILL_SUCCESS))
-            .execute(&conn)?;
+            if requested_game.status != GameStatus::FINISHED {
+                info!("Game {} is not finished yet. Cannot get game stats", code);
+                return Err(ModelError::GameNotFinished);
+            }
 
-            Ok(())
+            let standings = requested_game.standings(&conn)?;
+
+            let winner = standings
+                .iter()
+                .find(|(id, _)| requested_game.winner == Some(*id))
+                .map(|(_, stats)| stats.clone());
+
+            let ranking = standings.into_iter().map(|(_, stats)| stats).collect();
+
+            Ok(GameStats { winner, ranking })
         })
     }
 
-    //    pub fn game_stats(code: &String, player_id: i32) -> Result<()> {
-    //        //Diesel doesn't support GROUP BY queries in a many-to-many setting
-    //        //This means we have to dirty our hands with raw SQL queries...
-    //        let conn = db::connection()?;
+    /// Standings of the game, along with the id of each player. Agents are ranked by:
+    /// 1. score (highest first)
+    /// 2. kills (most first)
+    /// 3. deaths (fewest first)
+    /// 4. time of death (latest first)
+    /// 5. player id (lowest first), so that the ranking is always deterministic
+    pub(crate) fn standings(&self, conn: &PgConnection) -> Result<Vec<(i32, AgentStats)>> {
+        let standings = Game::ranked_agents(conn, &[self.id])?
+            .into_iter()
+            .map(|row| {
+                let stats = AgentStats {
+                    nickname: row.nickname,
+                    codename: row.codename,
+                    picture: row.picture,
+                    kills: row.kills,
+                    deaths: row.deaths,
+                    score: row.score,
+                };
+                (row.id, stats)
+            })
+            .collect();
+
+        Ok(standings)
+    }
 
-    //        conn.transaction(|| {
-    //            // let kill_counts: HashMap<i32, usize> = diesel::sql_query(
-    //            //     "SELECT (assassin, COUNT(*))
-    //            //     FROM assignment
-    //            //     WHERE status = 'KILL_SUCCESS'
-    //            //     GROUP BY assassin"
-    //            // ).load::<PlayerCount>(&conn).into_iter().collect();
+    /// Final place of the player in each of the given games, which must be over
+    pub(crate) fn placements_of(
+        conn: &PgConnection,
+        player_id: i32,
+        game_ids: &[i32],
+    ) -> QueryResult<HashMap<i32, usize>> {
+        Ok(Game::ranked_agents(conn, game_ids)?
+            .into_iter()
+            .filter(|row| row.id == player_id)
+            .filter_map(|row| {
+                let placement = usize::try_from(row.placement).ok()?;
+                Some((row.game, placement))
+            })
+            .collect())
+    }
 
-    //            // let death_counts: HashMap<i32, usize> = diesel::sql_query(
-    //            //     "SELECT (assassin, COUNT(*))
-    //            //     FROM assignment
-    //            //     WHERE status = 'KILL_SUCCESS'
-    //            //     GROUP BY target"
-    //            // ).load(&conn).into_iter.collect();
-    //            //
-    //            let kill_counts = assignment::table
-    //                .group_by(assignment::assassin)
-    //                .filter(assignment::status.eq(TargetStatus::KILL_SUCCESS))
-    //                .select((assignment::assassin, diesel::dsl::count(assignment::target)));
+    /// Every kill of the game, as `(assassin, target)` pairs
+    fn kills(&self, conn: &PgConnection) -> QueryResult<Vec<(i32, i32)>> {
+        assignment::table
+            .filter(assignment::game.eq(self.id))
+            .filter(assignment::status.eq(TargetStatus::KILL_SUCCESS))
+            .select((assignment::assassin, assignment::target))
+            .load(conn)
+    }
 
-    //            // info!("Got the following query: {}", diesel::debug_query::<DB, _>(&kill_counts));
+    /// The agents of the given games, ranked as described in `standings`, best first in each
+    /// game. The winner recorded for a game gets the points for winning it.
+    fn ranked_agents(conn: &PgConnection, game_ids: &[i32]) -> QueryResult<Vec<RankedAgentRow>> {
+        //Diesel doesn't support GROUP BY queries in a many-to-many setting
+        //This means we have to dirty our hands with raw SQL queries...
+        diesel::sql_query(
+            "SELECT game, id, nickname, codename, picture, kills, deaths, score,
+                ROW_NUMBER() OVER (
+                    PARTITION BY game
+                    ORDER BY score DESC, kills DESC, deaths ASC, died_at DESC NULLS LAST, id ASC
+                ) AS placement
+            FROM (
+                SELECT pg.game, p.id, p.nickname, pg.codename, p.picture,
+                    COALESCE(k.kills, 0) AS kills,
+                    COALESCE(d.deaths, 0) AS deaths,
+                    d.died_at,
+                    COALESCE(k.kills, 0) * s.score_per_kill
+                        + CASE WHEN pg.status = 'ALIVE' THEN s.score_for_surviving ELSE 0 END
+                        + CASE WHEN g.winner = pg.player THEN s.score_for_winning ELSE 0 END
+                        AS score
+                FROM playergame pg
+                INNER JOIN player p ON p.id = pg.player
+                INNER JOIN game g ON g.id = pg.game
+                INNER JOIN game_settings s ON s.game = pg.game
+                LEFT JOIN (
+                    SELECT game, assassin, COUNT(*) AS kills
+                    FROM assignment
+                    WHERE game = ANY($1) AND status = 'KILL_SUCCESS'
+                    GROUP BY game, assassin
+                ) k ON k.game = pg.game AND k.assassin = pg.player
+                LEFT JOIN (
+                    SELECT game, target, COUNT(*) AS deaths, MAX(end_time) AS died_at
+                    FROM assignment
+                    WHERE game = ANY($1) AND status = 'KILL_SUCCESS'
+                    GROUP BY game, target
+                ) d ON d.game = pg.game AND d.target = pg.player
+                WHERE pg.game = ANY($1)
+            ) agents
+            ORDER BY game, placement",
+        )
+        .bind::<Array<Integer>, _>(game_ids)
+        .load(conn)
+    }
 
-    //            // let requested_game: Game = game::table
-    //            //     .filter(game::code.eq(code))
-    //            //     .first(&conn)?;
+    /// Finishes all the active games whose end time has passed. The winner of each game is the
+    /// agent at the top of the final standings. Returns the number of games which were finished.
+    ///
+    /// Several server replicas may run this concurrently: a Postgres advisory lock makes sure
+    /// that only one of them does the work at a time.
+    pub fn expire_games() -> Result<usize> {
+        let conn = db::connection()?;
 
-    //            // let all_players = playergame::table
-    //            //     .inner_join(player::table)
-    //            //     .filter(playergame::game.eq(requested_game.id))
-    //            //     .select((player::id, player::nickname, playergame::codename, player::picture))
-    //            //     .load(&conn);
+        conn.transaction(|| {
+            let got_lock =
+                diesel::select(pg_try_advisory_xact_lock(constants::GAME_EXPIRY_LOCK_ID))
+                    .get_result::<bool>(&conn)?;
 
-    //            // let game_stats = GameStats {
+            if !got_lock {
+                info!("Another server is already expiring games");
+                return Ok(0);
+            }
 
-    //            // };
+            // Kill claims which timed out must go through before the games end
+            let games_with_expired_claims: Vec<String> = game::table
+                .inner_join(kill_claim::table)
+                .filter(game::status.eq(GameStatus::ACTIVE))
+                .filter(kill_claim::status.eq(KillClaimStatus::PENDING))
+                .filter(kill_claim::created_at.lt(KillClaim::deadline()))
+                .select(game::code)
+                .distinct()
+                .load(&conn)?;
 
-    //            Ok(())
-    //            // Ok(game_stats)
+            for code in games_with_expired_claims.iter() {
+                let settled = conn.transaction(|| Game::lock_and_settle(&conn, code));
 
-    //        })
+                if let Err(e) = settled {
+                    error!("Could not settle kill claims of game {}: {:?}", code, e);
+                }
+            }
 
-    //    }
+            let expired_games: Vec<Game> = game::table
+                .filter(game::status.eq(GameStatus::ACTIVE))
+                .filter(game::end_time.le(chrono::offset::Utc::now()))
+                .for_update()
+                .skip_locked()
+                .load(&conn)?;
+
+            let mut finished = 0;
+
+            for expired_game in expired_games.iter() {
+                // Each game is finished in its own savepoint, so that one failure doesn't
+                // prevent the others from ending
+                let res = conn.transaction(|| expired_game.finish_with_standings(&conn));
+
+                match res {
+                    Ok(()) => {
+                        info!("Game {} expired", expired_game.code);
+                        finished += 1;
+                    }
+                    Err(e) => error!("Could not expire game {}: {:?}", expired_game.code, e),
+                }
+            }
+
+            Ok(finished)
+        })
+    }
+
+    /// Finishes the game, declaring the agent at the top of the standings as the winner
+    pub(crate) fn finish_with_standings(&self, conn: &PgConnection) -> Result<()> {
+        let winner = self.standings(conn)?.first().map(|(id, _)| *id);
+        self.finish(conn, winner)?;
+        Ok(())
+    }
 }
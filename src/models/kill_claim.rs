@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::{Associations, Identifiable, Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::constants;
+use crate::models::enums::KillClaimStatus;
+use crate::models::game::Game;
+
+use crate::schema::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[table_name = "kill_claim"]
+pub struct NewKillClaim {
+    pub game: i32,
+    pub assassin: i32,
+    pub target: i32,
+}
+
+#[derive(Debug, Serialize, Associations, Deserialize, Queryable, Identifiable)]
+#[belongs_to(Game, foreign_key = "game")]
+#[table_name = "kill_claim"]
+pub struct KillClaim {
+    pub id: i32,
+    pub game: i32,
+    pub assassin: i32,
+    pub target: i32,
+    pub status: KillClaimStatus,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct DisputedKill {
+    pub claim_id: i32,
+    pub assassin_nickname: String,
+    pub target_nickname: String,
+    pub claimed_at: DateTime<Utc>,
+}
+
+impl KillClaim {
+    /// Fetches (and locks) the open claim on the given target, if any
+    pub fn open_on(
+        conn: &PgConnection,
+        game_id: i32,
+        target: i32,
+    ) -> QueryResult<Option<KillClaim>> {
+        kill_claim::table
+            .filter(kill_claim::game.eq(game_id))
+            .filter(kill_claim::target.eq(target))
+            .filter(
+                kill_claim::status
+                    .eq(KillClaimStatus::PENDING)
+                    .or(kill_claim::status.eq(KillClaimStatus::DISPUTED)),
+            )
+            .for_update()
+            .first(conn)
+            .optional()
+    }
+
+    /// Fetches (and locks) the open claims made by the given assassin
+    pub fn open_by(
+        conn: &PgConnection,
+        game_id: i32,
+        assassin: i32,
+    ) -> QueryResult<Vec<KillClaim>> {
+        kill_claim::table
+            .filter(kill_claim::game.eq(game_id))
+            .filter(kill_claim::assassin.eq(assassin))
+            .filter(
+                kill_claim::status
+                    .eq(KillClaimStatus::PENDING)
+                    .or(kill_claim::status.eq(KillClaimStatus::DISPUTED)),
+            )
+            .order(kill_claim::created_at.asc())
+            .for_update()
+            .load(conn)
+    }
+
+    /// Pending claims created before this instant have timed out
+    pub fn deadline() -> DateTime<Utc> {
+        chrono::offset::Utc::now()
+            - chrono::Duration::minutes(constants::KILL_CONFIRMATION_TIMEOUT_MINUTES)
+    }
+
+    /// Fetches (and locks) the pending claims which the victim hasn't answered in time
+    pub fn expired(conn: &PgConnection, game_id: i32) -> QueryResult<Vec<KillClaim>> {
+        kill_claim::table
+            .filter(kill_claim::game.eq(game_id))
+            .filter(kill_claim::status.eq(KillClaimStatus::PENDING))
+            .filter(kill_claim::created_at.lt(KillClaim::deadline()))
+            .order(kill_claim::created_at.asc())
+            .for_update()
+            .load(conn)
+    }
+
+    pub fn set_status(&self, conn: &PgConnection, status: KillClaimStatus) -> QueryResult<()> {
+        let resolved_at = match status {
+            KillClaimStatus::PENDING | KillClaimStatus::DISPUTED => None,
+            KillClaimStatus::CONFIRMED | KillClaimStatus::REJECTED => {
+                Some(chrono::offset::Utc::now())
+            }
+        };
+
+        diesel::update(self)
+            .set((
+                kill_claim::status.eq(status),
+                kill_claim::resolved_at.eq(resolved_at),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Datelike, Utc};
+use color_eyre::eyre::eyre;
+use diesel::pg::upsert::excluded;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable, Varchar};
+use diesel::Insertable;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::db;
+use crate::models::constants::{INITIAL_RATING, MAX_PAGE_SIZE};
+use crate::models::model_errors::{ModelError, Result};
+use crate::models::player::AgentStats;
+use crate::utils::rating::{self, RATING_SYSTEM};
+
+use crate::schema::*;
+
+/// Season under which the all-time rankings are kept
+const ALL_TIME: &str = "all-time";
+
+/// What the players are ranked by
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RankingMetric {
+    Kills,
+    Wins,
+    #[default]
+    Rating,
+}
+
+impl RankingMetric {
+    fn column(self) -> &'static str {
+        match self {
+            RankingMetric::Kills => "kills",
+            RankingMetric::Wins => "wins",
+            RankingMetric::Rating => "rating",
+        }
+    }
+}
+
+/// The standing of a player in a leaderboard. Players who are tied share the same rank.
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct LeaderboardEntry {
+    #[sql_type = "Integer"]
+    pub rank: i32,
+    #[sql_type = "Integer"]
+    pub player: i32,
+    #[sql_type = "Varchar"]
+    pub nickname: String,
+    #[sql_type = "Nullable<Varchar>"]
+    pub picture: Option<String>,
+    #[sql_type = "Integer"]
+    pub games_played: i32,
+    #[sql_type = "Integer"]
+    pub wins: i32,
+    #[sql_type = "Integer"]
+    pub kills: i32,
+    #[sql_type = "Integer"]
+    pub rating: i32,
+    #[serde(skip)]
+    #[sql_type = "Integer"]
+    position: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeaderboardPage {
+    pub season: Option<String>, // None for the all-time leaderboard
+    pub entries: Vec<LeaderboardEntry>,
+    pub next_cursor: Option<i32>, // Pass it as `after` to get the following page
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "leaderboard_entry"]
+struct Ranking {
+    season: String,
+    player: i32,
+    games_played: i32,
+    wins: i32,
+    kills: i32,
+    rating: i32,
+}
+
+/// Name of the season a point in time belongs to. Seasons last for a quarter, e.g. `2021-Q4`.
+pub fn season_of(at: DateTime<Utc>) -> String {
+    format!("{}-Q{}", at.year(), at.month0() / 3 + 1)
+}
+
+/// Fetches a page of the leaderboard of the given season, or the all-time one
+pub fn page(
+    season: Option<&str>,
+    by: RankingMetric,
+    after: i32,
+    limit: i64,
+) -> Result<LeaderboardPage> {
+    if limit <= 0 || limit > MAX_PAGE_SIZE {
+        return Err(ModelError::InvalidPagination);
+    }
+
+    let conn = db::connection()?;
+
+    let query = format!("{} LIMIT $3", ranking_query(by, "position > $2"));
+
+    let entries: Vec<LeaderboardEntry> = diesel::sql_query(query)
+        .bind::<Varchar, _>(season.unwrap_or(ALL_TIME))
+        .bind::<Integer, _>(after)
+        .bind::<BigInt, _>(limit)
+        .load(&conn)?;
+
+    let next_cursor = match entries.last() {
+        Some(last) if entries.len() as i64 == limit => Some(last.position),
+        _ => None,
+    };
+
+    Ok(LeaderboardPage {
+        season: season.map(String::from),
+        entries,
+        next_cursor,
+    })
+}
+
+/// Standing of the player in the leaderboard, unless they have no finished game in it
+pub fn rank_of(
+    player_id: i32,
+    season: Option<&str>,
+    by: RankingMetric,
+) -> Result<Option<LeaderboardEntry>> {
+    let conn = db::connection()?;
+
+    let entry = diesel::sql_query(ranking_query(by, "player = $2"))
+        .bind::<Varchar, _>(season.unwrap_or(ALL_TIME))
+        .bind::<Integer, _>(player_id)
+        .get_result(&conn)
+        .optional()?;
+
+    Ok(entry)
+}
+
+/// The seasons in which some game finished, latest first
+pub fn seasons() -> Result<Vec<String>> {
+    let conn = db::connection()?;
+
+    let seasons = leaderboard_entry::table
+        .filter(leaderboard_entry::season.ne(ALL_TIME))
+        .select(leaderboard_entry::season)
+        .distinct()
+        .order(leaderboard_entry::season.desc())
+        .load(&conn)?;
+
+    Ok(seasons)
+}
+
+/// Counts a finished game towards the all-time leaderboard and the one of the current season.
+/// The standings must be the final ones, best agent first, and the ratings the ones the players
+/// got for the game: they are the all-time ratings, while each season is rated on its own.
+pub(crate) fn record_game(
+    conn: &PgConnection,
+    standings: &[(i32, AgentStats)],
+    kills: &[(i32, i32)],
+    ratings: &[i32],
+    winner: Option<i32>,
+    finished_at: DateTime<Utc>,
+) -> Result<()> {
+    if standings.is_empty() {
+        return Ok(());
+    }
+
+    let players: Vec<i32> = standings.iter().map(|(id, _)| *id).collect();
+
+    // Rows are always created and locked in the same order, so that games finishing at the
+    // same time with players in common wait for each other instead of deadlocking
+    let mut sorted_players = players.clone();
+    sorted_players.sort_unstable();
+
+    for season in &[ALL_TIME.to_string(), season_of(finished_at)] {
+        let newcomers: Vec<Ranking> = sorted_players
+            .iter()
+            .map(|id| Ranking {
+                season: season.clone(),
+                player: *id,
+                games_played: 0,
+                wins: 0,
+                kills: 0,
+                rating: INITIAL_RATING,
+            })
+            .collect();
+
+        diesel::insert_into(leaderboard_entry::table)
+            .values(&newcomers)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        let before: HashMap<i32, i32> = leaderboard_entry::table
+            .filter(leaderboard_entry::season.eq(season))
+            .filter(leaderboard_entry::player.eq_any(&players))
+            .order(leaderboard_entry::player)
+            .select((leaderboard_entry::player, leaderboard_entry::rating))
+            .for_update()
+            .load::<(i32, i32)>(conn)?
+            .into_iter()
+            .collect();
+
+        let new_ratings = if season == ALL_TIME {
+            ratings.to_vec()
+        } else {
+            let before: Vec<i32> = players.iter().map(|id| before[id]).collect();
+            RATING_SYSTEM.rate(&rating::results(&players, &before, kills))
+        };
+
+        // What the game adds to the rankings of the players
+        let results = standings
+            .iter()
+            .zip(new_ratings)
+            .map(|((id, stats), new_rating)| {
+                let kills = i32::try_from(stats.kills).map_err(|_| {
+                    ModelError::UnknownError(eyre!(
+                        "User {} has too many kills to be ranked: {}",
+                        id,
+                        stats.kills
+                    ))
+                })?;
+
+                Ok(Ranking {
+                    season: season.clone(),
+                    player: *id,
+                    games_played: 1,
+                    wins: if winner == Some(*id) { 1 } else { 0 },
+                    kills,
+                    rating: new_rating,
+                })
+            })
+            .collect::<Result<Vec<Ranking>>>()?;
+
+        diesel::insert_into(leaderboard_entry::table)
+            .values(&results)
+            .on_conflict((leaderboard_entry::season, leaderboard_entry::player))
+            .do_update()
+            .set(
+                (
+                    leaderboard_entry::games_played
+                        .eq(leaderboard_entry::games_played
+                            + excluded(leaderboard_entry::games_played)),
+                    leaderboard_entry::wins
+                        .eq(leaderboard_entry::wins + excluded(leaderboard_entry::wins)),
+                    leaderboard_entry::kills
+                        .eq(leaderboard_entry::kills + excluded(leaderboard_entry::kills)),
+                    leaderboard_entry::rating.eq(excluded(leaderboard_entry::rating)),
+                ),
+            )
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Ranks the players of the leaderboard of the season given as first parameter, and keeps
+/// the ones matching the filter
+fn ranking_query(by: RankingMetric, filter: &str) -> String {
+    format!(
+        "SELECT * FROM (
+            SELECT l.player, p.nickname, p.picture, l.games_played, l.wins, l.kills, l.rating,
+                RANK() OVER (ORDER BY l.{metric} DESC)::INT AS rank,
+                ROW_NUMBER() OVER (ORDER BY l.{metric} DESC, l.player)::INT AS position
+            FROM leaderboard_entry l
+            INNER JOIN player p ON p.id = l.player
+            WHERE l.season = $1 AND p.deleted_at IS NULL
+        ) ranked
+        WHERE {filter}
+        ORDER BY position",
+        metric = by.column(),
+        filter = filter,
+    )
+}
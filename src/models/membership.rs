@@ -0,0 +1,123 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use tracing::info;
+
+use crate::models::enums::{GameStatus, PlayerStatus};
+use crate::models::game::Game;
+use crate::models::model_errors::{ModelError, Result};
+use crate::models::settings::GameRules;
+
+use crate::schema::*;
+
+/// A player let into a game by `admit`
+#[derive(Debug)]
+pub struct Admission {
+    pub game: Game,
+    pub is_late_join: bool, // The game is already going on, so the player needs a target
+}
+
+/// Checks that the player may create a new game. Must be called from the transaction
+/// which creates it.
+pub(crate) fn check_can_create(conn: &PgConnection, player_id: i32) -> Result<()> {
+    lock_player(conn, player_id)?;
+    check_not_in_another_game(conn, player_id)
+}
+
+/// Checks that the player may join the game with the given code, and returns the game. Must be
+/// called from the transaction which adds the player to it.
+///
+/// The player and the game are locked until the end of the transaction, so that concurrent
+/// requests can neither put the player in two games nor push the game over its capacity.
+pub(crate) fn admit(conn: &PgConnection, code: &str, player_id: i32) -> Result<Admission> {
+    let game: Game = game::table
+        .filter(game::code.eq(code))
+        .for_update()
+        .first(conn)
+        .map_err(|_| ModelError::GameNotFound)?;
+
+    // Games are always locked before their players, e.g. when they finish and players get rated
+    lock_player(conn, player_id)?;
+
+    // Players who left can't come back either, as their codename is taken for good
+    let is_member = playergame::table
+        .find((player_id, game.id))
+        .count()
+        .get_result::<i64>(conn)?
+        > 0;
+
+    if is_member {
+        info!("User {} is already in game {}", player_id, code);
+        return Err(ModelError::AlreadyInRequestedGame);
+    }
+
+    let is_late_join = match game.status {
+        GameStatus::WAITING_FOR_PLAYERS => false,
+        GameStatus::ACTIVE | GameStatus::PAUSED => true,
+        GameStatus::FINISHED => {
+            info!("Game {} is over. Cannot join it", code);
+            return Err(ModelError::GameNotActive);
+        }
+    };
+
+    if is_late_join && !GameRules::of(conn, game.id)?.allow_late_join {
+        info!("Game {} doesn't allow late joining", code);
+        return Err(ModelError::GameAlreadyStarted);
+    }
+
+    let is_banned = game_ban::table
+        .find((game.id, player_id))
+        .count()
+        .get_result::<i64>(conn)?
+        > 0;
+
+    if is_banned {
+        info!("User {} is banned from game {}", player_id, code);
+        return Err(ModelError::BannedFromGame);
+    }
+
+    check_not_in_another_game(conn, player_id)?;
+
+    if member_count(conn, game.id)? >= i64::from(game.max_players) {
+        info!("Game {} is full", code);
+        return Err(ModelError::GameFull);
+    }
+
+    Ok(Admission { game, is_late_join })
+}
+
+/// Number of players who are in the game and didn't leave it
+pub(crate) fn member_count(conn: &PgConnection, game_id: i32) -> QueryResult<i64> {
+    playergame::table
+        .filter(playergame::game.eq(game_id))
+        .filter(playergame::status.ne(PlayerStatus::LEFT_GAME))
+        .count()
+        .get_result(conn)
+}
+
+/// Players may only be in one game at a time, whether it is waiting for players or going on
+fn check_not_in_another_game(conn: &PgConnection, player_id: i32) -> Result<()> {
+    let other_games = playergame::table
+        .inner_join(game::table)
+        .filter(playergame::player.eq(player_id))
+        .filter(playergame::status.ne(PlayerStatus::LEFT_GAME))
+        .filter(game::status.ne(GameStatus::FINISHED))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    if other_games > 0 {
+        info!("User {} is already in another game", player_id);
+        return Err(ModelError::AlreadyInAnotherGame);
+    }
+
+    Ok(())
+}
+
+/// Serializes the membership changes of the player
+fn lock_player(conn: &PgConnection, player_id: i32) -> QueryResult<()> {
+    player::table
+        .find(player_id)
+        .select(player::id)
+        .for_update()
+        .first::<i32>(conn)?;
+    Ok(())
+}
@@ -1,6 +1,15 @@
+pub mod admin;
 pub mod api_errors;
+pub mod assignment;
 pub mod enums;
+pub mod events;
 pub mod game;
+pub mod kill_claim;
+pub mod leaderboard;
+pub mod membership;
 pub mod model_errors;
 pub mod player;
+pub mod rating;
+pub mod settings;
+pub mod stats;
 pub mod constants;
@@ -16,8 +16,50 @@ pub enum ModelError {
     GameNotStarted,
     #[error("Game not found")]
     GameNotFound,
+    #[error("Game is not active")]
+    GameNotActive,
+    #[error("Game is paused")]
+    GamePaused,
+    #[error("Game is not paused")]
+    GameNotPaused,
+    #[error("Game hasn't finished yet")]
+    GameNotFinished,
+    #[error("The requested game settings are not valid")]
+    InvalidSettings,
+    #[error("Game has already started")]
+    GameAlreadyStarted,
+    #[error("Only the game owner can perform this action")]
+    NotGameOwner,
+    #[error("The owner cannot do this to themselves")]
+    CannotModerateSelf,
+    #[error("The player is banned from the requested game")]
+    BannedFromGame,
+    #[error("The game has no free slots left")]
+    GameFull,
+    #[error("Only admins can perform this action")]
+    NotAdmin,
+    #[error("The server can't be left without any admin")]
+    LastAdmin,
+    #[error("Player not found")]
+    PlayerNotFound,
+    #[error("Not enough players to start the game")]
+    NotEnoughPlayers,
     #[error("The player doesn't currently have a target")]
     NoCurrentTarget,
+    #[error("A kill on this target has already been claimed")]
+    KillAlreadyClaimed,
+    #[error("There is no pending kill claim against the player")]
+    NoPendingKillClaim,
+    #[error("Kill claim not found")]
+    KillClaimNotFound,
+    #[error("The requested page is not valid")]
+    InvalidPagination,
+    #[error("The nickname is already taken")]
+    NicknameTaken,
+    #[error("The nickname is not valid")]
+    InvalidNickname,
+    #[error("The picture is not valid")]
+    InvalidPicture,
     #[error("User is already registered")]
     AlreadyRegistered,
     #[error("You are not registered yet")]
@@ -37,7 +79,28 @@ impl ModelError {
             Self::NotInGame => "NOT_IN_GAME".to_string(),
             Self::GameNotStarted => "GAME_NOT_STARTED".to_string(),
             Self::GameNotFound => "GAME_NOT_FOUND".to_string(),
+            Self::GameNotActive => "GAME_NOT_ACTIVE".to_string(),
+            Self::GamePaused => "GAME_PAUSED".to_string(),
+            Self::GameNotPaused => "GAME_NOT_PAUSED".to_string(),
+            Self::GameNotFinished => "GAME_NOT_FINISHED".to_string(),
+            Self::InvalidSettings => "INVALID_SETTINGS".to_string(),
+            Self::GameAlreadyStarted => "GAME_ALREADY_STARTED".to_string(),
+            Self::NotGameOwner => "NOT_GAME_OWNER".to_string(),
+            Self::CannotModerateSelf => "CANNOT_MODERATE_SELF".to_string(),
+            Self::BannedFromGame => "BANNED_FROM_GAME".to_string(),
+            Self::GameFull => "GAME_FULL".to_string(),
+            Self::NotAdmin => "NOT_ADMIN".to_string(),
+            Self::LastAdmin => "LAST_ADMIN".to_string(),
+            Self::PlayerNotFound => "PLAYER_NOT_FOUND".to_string(),
+            Self::NotEnoughPlayers => "NOT_ENOUGH_PLAYERS".to_string(),
             Self::NoCurrentTarget => "NO_CURRENT_TARGET".to_string(),
+            Self::KillAlreadyClaimed => "KILL_ALREADY_CLAIMED".to_string(),
+            Self::NoPendingKillClaim => "NO_PENDING_KILL_CLAIM".to_string(),
+            Self::KillClaimNotFound => "KILL_CLAIM_NOT_FOUND".to_string(),
+            Self::InvalidPagination => "INVALID_PAGINATION".to_string(),
+            Self::NicknameTaken => "NICKNAME_TAKEN".to_string(),
+            Self::InvalidNickname => "INVALID_NICKNAME".to_string(),
+            Self::InvalidPicture => "INVALID_PICTURE".to_string(),
             Self::AlreadyRegistered => "ALREADY_REGISTERED".to_string(),
             Self::NotRegistered => "NOT_REGISTERED".to_string(),
             Self::UnknownError(_) => "UNKNOWN".to_string(),
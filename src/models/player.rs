@@ -1,18 +1,24 @@
 use actix_web::{dev::Payload, FromRequest, HttpRequest};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind::UniqueViolation, Error::DatabaseError};
 use diesel::{Identifiable, Insertable, Queryable};
 use futures_util::future::{err, ok, Ready};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use tracing::error;
+use tracing::{error, info};
 
 use crate::db;
 use crate::models::api_errors::ApiError;
-use crate::models::enums::{GameStatus, PlayerStatus, Role, TargetStatus};
-use crate::models::game::Game;
+use crate::models::assignment::Assignment;
+use crate::models::constants::{MAX_NICKNAME_LENGTH, MAX_PAGE_SIZE, RECENT_RATING_CHANGES};
+use crate::models::enums::{GameStatus, KillClaimStatus, PlayerStatus, Role, TargetStatus};
+use crate::models::game::{Game, PlayerGame};
 use crate::models::model_errors::{ModelError, Result};
+use crate::models::rating::{self, RatingChange};
 use crate::utils::auth;
+use crate::utils::pictures::{self, Picture, StoredPicture};
 
 use crate::schema::*;
 
@@ -35,6 +41,8 @@ pub struct Player {
     pub role: Role,
     pub picture: Option<String>,
     pub registered_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub rating: i32,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,6 +53,52 @@ pub struct UserInfo {
     pub active: bool,
     pub curr_lobby_code: Option<String>,
     pub total_kills: usize,
+    pub rating: i32,
+    pub rating_history: Vec<RatingChange>, // Most recent first
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfilePicture {
+    pub picture: String,
+    pub thumbnail: String,
+}
+
+/// Everything we store about a player, as handed to them on request
+#[derive(Debug, Serialize)]
+pub struct DataExport {
+    pub exported_at: DateTime<Utc>,
+    pub player: Player,
+    pub games: Vec<GameRecord>,
+    pub rating_history: Vec<RatingChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameRecord {
+    pub game: Game,
+    pub membership: PlayerGame,
+    pub assignments: Vec<Assignment>,
+}
+
+/// A game the player took part in, as seen from their side
+#[derive(Debug, Serialize)]
+pub struct GameHistoryEntry {
+    pub game_code: String,
+    pub game_name: Option<String>,
+    pub game_status: GameStatus,
+    pub joined_at: DateTime<Utc>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub codename: String,
+    pub status: PlayerStatus,
+    pub kills: usize,
+    pub eliminated_by: Option<String>, // Nickname of the killer, revealed once the game is over
+    pub placement: Option<usize>,      // Starting from 1, once the game is over
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameHistoryPage {
+    pub games: Vec<GameHistoryEntry>,
+    pub next_cursor: Option<i32>, // Pass it as `after` to get the following page
 }
 
 #[derive(Debug, Serialize)]
@@ -54,16 +108,17 @@ pub struct AgentInfo {
     target_picture: Option<String>, //See discussion on nullable picture
     alive: bool,
     kills: usize,
+    pending_kill_claim: bool, //Someone claims to have killed this agent
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AgentStats {
-    nickname: String,
-    codename: String,
-    picture: Option<String>,
-    kills: usize,
-    deaths: usize,
-    score: usize,
+    pub nickname: String,
+    pub codename: String,
+    pub picture: Option<String>,
+    pub kills: i64,
+    pub deaths: i64,
+    pub score: i64,
 }
 
 impl Player {
@@ -78,7 +133,10 @@ impl Player {
     pub fn find_by_uid(uid: &String) -> Result<Self> {
         let conn = db::connection()?;
 
-        let account = player::table.filter(player::uid.eq(uid)).first(&conn)?;
+        let account = player::table
+            .filter(player::uid.eq(uid))
+            .filter(player::deleted_at.is_null())
+            .first(&conn)?;
 
         Ok(account)
     }
@@ -96,11 +154,86 @@ impl Player {
         let res = diesel::insert_into(player::table)
             .values(new_account.clone())
             .get_result(&conn)
-            .map_err(|_| ModelError::AlreadyRegistered)?;
+            .map_err(|e| match e {
+                e if is_nickname_conflict(&e) => ModelError::NicknameTaken,
+                _ => ModelError::AlreadyRegistered,
+            })?;
 
         Ok(res)
     }
 
+    pub fn update_nickname(&self, nickname: &str) -> Result<()> {
+        let nickname = nickname.trim();
+
+        if nickname.is_empty() || nickname.chars().count() > MAX_NICKNAME_LENGTH {
+            return Err(ModelError::InvalidNickname);
+        }
+
+        let conn = db::connection()?;
+
+        diesel::update(self)
+            .set(player::nickname.eq(nickname))
+            .execute(&conn)
+            .map_err(|e| match e {
+                e if is_nickname_conflict(&e) => ModelError::NicknameTaken,
+                e => e.into(),
+            })?;
+
+        info!("User {} is now known as {}", self.id, nickname);
+        Ok(())
+    }
+
+    /// Replaces the profile picture of the player with the uploaded image
+    pub fn update_picture(&self, upload: &[u8]) -> Result<ProfilePicture> {
+        let picture = Picture::from_upload(upload).map_err(|e| {
+            info!("User {} uploaded a bad picture: {}", self.id, e);
+            ModelError::InvalidPicture
+        })?;
+
+        let StoredPicture { url, thumbnail_url } =
+            picture.store(self.id).map_err(ModelError::UnknownError)?;
+
+        let swap = || -> Result<Option<String>> {
+            let conn = db::connection()?;
+            conn.transaction(|| {
+                // Another upload may have replaced the picture since the player was loaded
+                let old: Option<String> = player::table
+                    .find(self.id)
+                    .select(player::picture)
+                    .for_update()
+                    .first(&conn)?;
+
+                diesel::update(self)
+                    .set(player::picture.eq(&url))
+                    .execute(&conn)?;
+
+                Ok(old)
+            })
+        };
+
+        // Nothing points to the new files unless the swap went through
+        let (leftover, swapped) = match swap() {
+            Ok(old) => (old, Ok(())),
+            Err(e) => (Some(url.clone()), Err(e)),
+        };
+
+        if let Some(leftover) = leftover {
+            if let Err(e) = pictures::remove(&leftover) {
+                error!(
+                    "Could not delete picture {} of user {}: {:?}",
+                    leftover, self.id, e
+                );
+            }
+        }
+
+        swapped?;
+
+        Ok(ProfilePicture {
+            picture: url,
+            thumbnail: thumbnail_url,
+        })
+    }
+
     pub fn get_user_info(&self) -> Result<UserInfo> {
         //Info about email, username, propic are already in `self`, so we query only the remaining
 
@@ -142,17 +275,233 @@ impl Player {
                 active: has_active_game,
                 curr_lobby_code: active_game_code,
                 total_kills: usize::try_from(total_kills).unwrap(),
+                rating: self.rating,
+                rating_history: rating::history(&conn, self.id, RECENT_RATING_CHANGES)?,
             };
 
             Ok(user_info)
         })
     }
 
+    /// Deletes the account of the player, taking them out of the games they are still in.
+    /// The row is anonymized rather than removed, since the history of past games refers to it.
+    pub fn delete_account(&self) -> Result<()> {
+        let conn = db::connection()?;
+        let res: Result<()> = conn.transaction(|| {
+            // Dead agents stay as they are, so that the history of their games doesn't change
+            let active_games: Vec<String> = playergame::table
+                .inner_join(game::table)
+                .filter(playergame::player.eq(self.id))
+                .filter(playergame::status.eq(PlayerStatus::ALIVE))
+                .filter(game::status.ne(GameStatus::FINISHED))
+                .select(game::code)
+                .load(&conn)?;
+
+            for code in active_games.iter() {
+                let requested_game = Game::lock_and_settle(&conn, code)?;
+
+                // Settling the pending kill claims may have ended the game, or killed the player
+                let status: PlayerStatus = playergame::table
+                    .find((self.id, requested_game.id))
+                    .select(playergame::status)
+                    .first(&conn)?;
+
+                if requested_game.status != GameStatus::FINISHED && status == PlayerStatus::ALIVE {
+                    requested_game.remove_player(&conn, self.id)?;
+                }
+            }
+
+            diesel::update(self)
+                .set((
+                    player::nickname.eq(format!("deleted-{}", self.id)),
+                    player::email.eq(format!("deleted-{}@invalid", self.id)),
+                    player::uid.eq(format!("deleted:{}", self.id)),
+                    player::picture.eq(None::<String>),
+                    player::role.eq(Role::USER),
+                    player::deleted_at.eq(chrono::offset::Utc::now()),
+                ))
+                .execute(&conn)?;
+
+            Ok(())
+        });
+
+        res?;
+
+        if let Some(picture) = &self.picture {
+            if let Err(e) = pictures::remove(picture) {
+                error!("Could not delete picture of user {}: {:?}", self.id, e);
+            }
+        }
+
+        info!("User {} deleted their account", self.id);
+        Ok(())
+    }
+
+    /// The games the player joined, latest first
+    pub fn game_history(&self, after: Option<i32>, limit: i64) -> Result<GameHistoryPage> {
+        if limit <= 0 || limit > MAX_PAGE_SIZE {
+            return Err(ModelError::InvalidPagination);
+        }
+
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let mut query = playergame::table
+                .inner_join(game::table)
+                .filter(playergame::player.eq(self.id))
+                .order(game::id.desc())
+                .limit(limit)
+                .into_boxed();
+
+            if let Some(after) = after {
+                query = query.filter(game::id.lt(after));
+            }
+
+            let memberships: Vec<(PlayerGame, Game)> = query.load(&conn)?;
+            let game_ids: Vec<i32> = memberships.iter().map(|(_, game)| game.id).collect();
+
+            let mut kills: HashMap<i32, usize> = HashMap::new();
+            for game_id in assignment::table
+                .filter(assignment::game.eq_any(&game_ids))
+                .filter(assignment::assassin.eq(self.id))
+                .filter(assignment::status.eq(TargetStatus::KILL_SUCCESS))
+                .select(assignment::game)
+                .load::<i32>(&conn)?
+            {
+                *kills.entry(game_id).or_default() += 1;
+            }
+
+            // Later deaths override the earlier ones
+            let killers: HashMap<i32, String> = assignment::table
+                .inner_join(player::table.on(player::id.eq(assignment::assassin)))
+                .filter(assignment::game.eq_any(&game_ids))
+                .filter(assignment::target.eq(self.id))
+                .filter(assignment::status.eq(TargetStatus::KILL_SUCCESS))
+                .order(assignment::end_time.asc())
+                .select((assignment::game, player::nickname))
+                .load::<(i32, String)>(&conn)?
+                .into_iter()
+                .collect();
+
+            let is_over =
+                |game: &Game| game.status == GameStatus::FINISHED && game.start_time.is_some();
+
+            let over_game_ids: Vec<i32> = memberships
+                .iter()
+                .map(|(_, game)| game)
+                .filter(|game| is_over(game))
+                .map(|game| game.id)
+                .collect();
+
+            let placements = Game::placements_of(&conn, self.id, &over_game_ids)?;
+
+            let next_cursor = match memberships.last() {
+                Some((_, last)) if memberships.len() as i64 == limit => Some(last.id),
+                _ => None,
+            };
+
+            let mut games = Vec::with_capacity(memberships.len());
+
+            for (membership, game) in memberships {
+                let (eliminated_by, placement) = if is_over(&game) {
+                    (
+                        killers.get(&game.id).cloned(),
+                        placements.get(&game.id).copied(),
+                    )
+                } else {
+                    (None, None)
+                };
+
+                games.push(GameHistoryEntry {
+                    kills: kills.get(&game.id).copied().unwrap_or(0),
+                    eliminated_by,
+                    placement,
+                    game_code: game.code,
+                    game_name: game.name,
+                    game_status: game.status,
+                    joined_at: membership.joined_at,
+                    start_time: game.start_time,
+                    end_time: game.end_time,
+                    codename: membership.codename,
+                    status: membership.status,
+                });
+            }
+
+            Ok(GameHistoryPage { games, next_cursor })
+        })
+    }
+
+    /// Gathers everything stored about the player. Who was hunting them is left out
+    /// of the games which are still going on.
+    pub fn export_data(&self) -> Result<DataExport> {
+        let conn = db::connection()?;
+
+        conn.transaction(|| {
+            let player: Player = player::table.find(self.id).first(&conn)?;
+
+            let memberships: Vec<(PlayerGame, Game)> = playergame::table
+                .inner_join(game::table)
+                .filter(playergame::player.eq(self.id))
+                .order(game::created_at.asc())
+                .load(&conn)?;
+
+            let mut assignments: HashMap<i32, Vec<Assignment>> = HashMap::new();
+
+            for assignment in assignment::table
+                .filter(
+                    assignment::assassin
+                        .eq(self.id)
+                        .or(assignment::target.eq(self.id)),
+                )
+                .order(assignment::start_time.asc())
+                .load::<Assignment>(&conn)?
+            {
+                assignments
+                    .entry(assignment.game)
+                    .or_default()
+                    .push(assignment);
+            }
+
+            let games = memberships
+                .into_iter()
+                .map(|(membership, game)| {
+                    let finished = game.status == GameStatus::FINISHED;
+                    let assignments = assignments
+                        .remove(&game.id)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|a| finished || a.assassin == self.id)
+                        .collect();
+
+                    GameRecord {
+                        game,
+                        membership,
+                        assignments,
+                    }
+                })
+                .collect();
+
+            let rating_history = rating::history(&conn, self.id, i64::MAX)?;
+
+            Ok(DataExport {
+                exported_at: chrono::offset::Utc::now(),
+                player,
+                games,
+                rating_history,
+            })
+        })
+    }
+
     pub fn get_agent_info(&self, code: &String) -> Result<AgentInfo> {
         let conn = db::connection()?;
         conn.transaction(|| {
             //TODO: Should check here whether the game is finished or not?
-            let requested_game: Game = game::table.filter(game::code.eq(code)).first(&conn)?;
+            // Clients poll this, so it mustn't wait on the game lock: the kill claims which
+            // timed out are settled by the next write to the game or by the expiry task
+            let requested_game: Game = game::table
+                .filter(game::code.eq(code))
+                .first(&conn)
+                .map_err(|_| ModelError::GameNotFound)?;
 
             let (codename, status): (String, PlayerStatus) = playergame::table
                 .filter(playergame::player.eq(self.id))
@@ -181,12 +530,21 @@ impl Player {
                 .count()
                 .get_result::<i64>(&conn)?;
 
+            let pending_kill_claim = kill_claim::table
+                .filter(kill_claim::game.eq(requested_game.id))
+                .filter(kill_claim::target.eq(self.id))
+                .filter(kill_claim::status.eq(KillClaimStatus::PENDING))
+                .count()
+                .get_result::<i64>(&conn)?
+                > 0;
+
             let agent_info = AgentInfo {
                 codename,
                 target: target_nickname,
                 target_picture,
                 alive,
                 kills: usize::try_from(kills).unwrap(),
+                pending_kill_claim,
             };
 
             Ok(agent_info)
@@ -194,6 +552,15 @@ impl Player {
     }
 }
 
+fn is_nickname_conflict(e: &diesel::result::Error) -> bool {
+    match e {
+        DatabaseError(UniqueViolation, info) => {
+            info.constraint_name() == Some("player_nickname_key")
+        }
+        _ => false,
+    }
+}
+
 impl FromRequest for Player {
     type Error = ApiError;
     type Future = Ready<std::result::Result<Self, Self::Error>>;
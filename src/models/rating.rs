@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::{Insertable, Queryable};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::utils::rating::{self, RATING_SYSTEM};
+
+use crate::schema::*;
+
+/// How a game changed the rating of a player
+#[derive(Debug, Serialize, Queryable)]
+pub struct RatingChange {
+    pub game_code: String,
+    pub game_name: Option<String>,
+    pub rating_before: i32,
+    pub rating_after: i32,
+    pub rated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "rating_history"]
+struct NewRatingChange {
+    player: i32,
+    game: i32,
+    rating_before: i32,
+    rating_after: i32,
+}
+
+/// Updates the ratings of the players of a finished game, who must be sorted by final
+/// standing. Kills are given as `(assassin, target)` pairs. Returns the new ratings of
+/// the players, in the same order.
+pub(crate) fn rate_game(
+    conn: &PgConnection,
+    game_id: i32,
+    players: &[i32],
+    kills: &[(i32, i32)],
+) -> QueryResult<Vec<i32>> {
+    if players.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Locked in a fixed order, like the leaderboard rows, to avoid deadlocks
+    let current: HashMap<i32, i32> = player::table
+        .filter(player::id.eq_any(players))
+        .order(player::id)
+        .select((player::id, player::rating))
+        .for_update()
+        .load::<(i32, i32)>(conn)?
+        .into_iter()
+        .collect();
+
+    let before: Vec<i32> = players.iter().map(|id| current[id]).collect();
+    let after = RATING_SYSTEM.rate(&rating::results(players, &before, kills));
+
+    for (id, rating) in players.iter().zip(after.iter()) {
+        diesel::update(player::table.find(id))
+            .set(player::rating.eq(rating))
+            .execute(conn)?;
+    }
+
+    let changes: Vec<NewRatingChange> = players
+        .iter()
+        .zip(before.iter().zip(after.iter()))
+        .map(|(id, (rating_before, rating_after))| NewRatingChange {
+            player: *id,
+            game: game_id,
+            rating_before: *rating_before,
+            rating_after: *rating_after,
+        })
+        .collect();
+
+    diesel::insert_into(rating_history::table)
+        .values(&changes)
+        .execute(conn)?;
+
+    Ok(after)
+}
+
+/// The latest rating changes of the player, most recent first
+pub fn history(conn: &PgConnection, player_id: i32, limit: i64) -> QueryResult<Vec<RatingChange>> {
+    rating_history::table
+        .inner_join(game::table)
+        .filter(rating_history::player.eq(player_id))
+        .order(rating_history::created_at.desc())
+        .select((
+            game::code,
+            game::name,
+            rating_history::rating_before,
+            rating_history::rating_after,
+            rating_history::created_at,
+        ))
+        .limit(limit)
+        .load(conn)
+}
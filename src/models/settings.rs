@@ -0,0 +1,115 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::{AsChangeset, Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::constants;
+use crate::models::enums::KillConfirmation;
+use crate::models::model_errors::{ModelError, Result};
+
+use crate::schema::*;
+
+/// Rules of a game, stored in `game_settings`
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable, AsChangeset)]
+#[table_name = "game_settings"]
+#[serde(default)]
+pub struct GameRules {
+    pub duration_minutes: i32,
+    pub min_players: i32,
+    pub allow_late_join: bool,
+    pub kill_confirmation: KillConfirmation,
+    pub score_per_kill: i32,
+    pub score_for_surviving: i32,
+    pub score_for_winning: i32,
+    pub is_public: bool, // Listed in the lobbies anybody can browse
+}
+
+/// Settings chosen by the game owner. The maximum number of players lives in the `game` table,
+/// while everything else is stored in `game_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSettings {
+    #[serde(default = "default_max_players")]
+    pub max_players: i32,
+    #[serde(flatten)]
+    pub rules: GameRules,
+}
+
+type RulesColumns = (
+    game_settings::duration_minutes,
+    game_settings::min_players,
+    game_settings::allow_late_join,
+    game_settings::kill_confirmation,
+    game_settings::score_per_kill,
+    game_settings::score_for_surviving,
+    game_settings::score_for_winning,
+    game_settings::is_public,
+);
+
+const RULES_COLUMNS: RulesColumns = (
+    game_settings::duration_minutes,
+    game_settings::min_players,
+    game_settings::allow_late_join,
+    game_settings::kill_confirmation,
+    game_settings::score_per_kill,
+    game_settings::score_for_surviving,
+    game_settings::score_for_winning,
+    game_settings::is_public,
+);
+
+fn default_max_players() -> i32 {
+    constants::DEFAULT_MAX_PLAYERS
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules {
+            duration_minutes: constants::DEFAULT_GAME_DURATION_MINUTES,
+            min_players: constants::DEFAULT_MIN_PLAYERS,
+            allow_late_join: false,
+            kill_confirmation: KillConfirmation::VICTIM,
+            score_per_kill: constants::DEFAULT_SCORE_PER_KILL,
+            score_for_surviving: constants::DEFAULT_SCORE_FOR_SURVIVING,
+            score_for_winning: constants::DEFAULT_SCORE_FOR_WINNING,
+            is_public: false,
+        }
+    }
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            max_players: default_max_players(),
+            rules: GameRules::default(),
+        }
+    }
+}
+
+impl GameRules {
+    pub fn of(conn: &PgConnection, game_id: i32) -> QueryResult<GameRules> {
+        game_settings::table
+            .find(game_id)
+            .select(RULES_COLUMNS)
+            .first(conn)
+    }
+}
+
+impl GameSettings {
+    pub fn validate(&self) -> Result<()> {
+        let rules = &self.rules;
+
+        let is_valid = rules.min_players >= 2
+            && rules.min_players <= self.max_players
+            && self.max_players <= constants::MAX_PLAYERS_LIMIT
+            && rules.duration_minutes >= constants::MIN_GAME_DURATION_MINUTES
+            && rules.duration_minutes <= constants::MAX_GAME_DURATION_MINUTES
+            && rules.score_per_kill >= 0
+            && rules.score_for_surviving >= 0
+            && rules.score_for_winning >= 0;
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(ModelError::InvalidSettings)
+        }
+    }
+}
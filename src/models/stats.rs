@@ -0,0 +1,101 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable};
+use serde::Serialize;
+use tracing::info;
+
+use crate::db;
+use crate::models::model_errors::{ModelError, Result};
+use crate::models::player::Player;
+
+use crate::schema::*;
+
+/// Career statistics of a player over the games which are over
+#[derive(Debug, Serialize)]
+pub struct CareerStats {
+    pub nickname: String,
+    pub picture: Option<String>,
+    pub games_played: i64,
+    pub wins: i64,
+    pub kills: i64,
+    pub deaths: i64,
+    pub kd_ratio: f64,
+    pub longest_survival_seconds: Option<i64>,
+    pub average_seconds_to_kill: Option<i64>,
+}
+
+#[derive(Debug, QueryableByName)]
+struct PlayerStatsRow {
+    #[sql_type = "BigInt"]
+    games_played: i64,
+    #[sql_type = "BigInt"]
+    wins: i64,
+    #[sql_type = "BigInt"]
+    kills: i64,
+    #[sql_type = "BigInt"]
+    deaths: i64,
+    #[sql_type = "Nullable<BigInt>"]
+    longest_survival_seconds: Option<i64>,
+    #[sql_type = "Nullable<BigInt>"]
+    average_seconds_to_kill: Option<i64>,
+}
+
+impl CareerStats {
+    pub fn of(player_id: i32) -> Result<Self> {
+        let conn = db::connection()?;
+
+        let player: Player = player::table
+            .find(player_id)
+            .filter(player::deleted_at.is_null())
+            .first(&conn)
+            .map_err(|_| ModelError::PlayerNotFound)?;
+
+        // Players who registered after the last refresh are not in the view yet
+        let row: Option<PlayerStatsRow> = diesel::sql_query(
+            "SELECT games_played, wins, kills, deaths, longest_survival_seconds,
+                average_seconds_to_kill
+            FROM player_stats
+            WHERE player = $1",
+        )
+        .bind::<Integer, _>(player.id)
+        .get_result(&conn)
+        .optional()?;
+
+        let stats = match row {
+            Some(row) => CareerStats {
+                nickname: player.nickname,
+                picture: player.picture,
+                games_played: row.games_played,
+                wins: row.wins,
+                kills: row.kills,
+                deaths: row.deaths,
+                // Players who never died get their kills as ratio
+                kd_ratio: row.kills as f64 / row.deaths.max(1) as f64,
+                longest_survival_seconds: row.longest_survival_seconds,
+                average_seconds_to_kill: row.average_seconds_to_kill,
+            },
+            None => CareerStats {
+                nickname: player.nickname,
+                picture: player.picture,
+                games_played: 0,
+                wins: 0,
+                kills: 0,
+                deaths: 0,
+                kd_ratio: 0.0,
+                longest_survival_seconds: None,
+                average_seconds_to_kill: None,
+            },
+        };
+
+        Ok(stats)
+    }
+
+    /// Recomputes the statistics of everybody. Readers are not blocked in the meantime.
+    pub fn refresh() -> Result<()> {
+        let conn = db::connection()?;
+
+        diesel::sql_query("REFRESH MATERIALIZED VIEW CONCURRENTLY player_stats").execute(&conn)?;
+
+        info!("Refreshed player statistics");
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use actix_web::{get, post, web, HttpResponse};
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::models::admin::AdminPlayer;
+use crate::models::api_errors::ApiError;
+use crate::models::constants::DEFAULT_PAGE_SIZE;
+use crate::models::enums::{GameStatus, Role};
+
+type HttpResult = std::result::Result<HttpResponse, ApiError>;
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerSearchInfo {
+    search: Option<String>,
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[get("/players")]
+#[instrument]
+pub async fn list_players(admin: AdminPlayer, info: web::Query<PlayerSearchInfo>) -> HttpResult {
+    let page = admin.players(
+        info.search.as_deref(),
+        info.after.unwrap_or(0),
+        info.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GameSearchInfo {
+    search: Option<String>,
+    status: Option<GameStatus>,
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[get("/games")]
+#[instrument]
+pub async fn list_games(admin: AdminPlayer, info: web::Query<GameSearchInfo>) -> HttpResult {
+    let info = info.into_inner();
+    let page = admin.games(
+        info.search.as_deref(),
+        info.status,
+        info.after.unwrap_or(0),
+        info.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditPageInfo {
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[get("/actions")]
+#[instrument]
+pub async fn list_actions(admin: AdminPlayer, info: web::Query<AuditPageInfo>) -> HttpResult {
+    let page = admin.actions(
+        info.after.unwrap_or(0),
+        info.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GameInfo {
+    #[serde(rename = "gameCode")]
+    game_code: String,
+}
+
+#[post("/end_game")]
+#[instrument]
+pub async fn end_game(admin: AdminPlayer, info: web::Query<GameInfo>) -> HttpResult {
+    admin.end_game(&info.game_code)?;
+    info!("Admin {} ended game {}", admin.0.id, info.game_code);
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/pause_game")]
+#[instrument]
+pub async fn pause(admin: AdminPlayer, info: web::Query<GameInfo>) -> HttpResult {
+    admin.pause_game(&info.game_code)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/resume_game")]
+#[instrument]
+pub async fn resume(admin: AdminPlayer, info: web::Query<GameInfo>) -> HttpResult {
+    admin.resume_game(&info.game_code)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KickInfo {
+    #[serde(rename = "gameCode")]
+    game_code: String,
+    #[serde(rename = "playerId")]
+    player_id: i32,
+}
+
+#[post("/kick")]
+#[instrument]
+pub async fn kick(admin: AdminPlayer, info: web::Query<KickInfo>) -> HttpResult {
+    admin.kick_player(&info.game_code, info.player_id)?;
+    info!(
+        "Admin {} kicked user {} out of game {}",
+        admin.0.id, info.player_id, info.game_code
+    );
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleInfo {
+    #[serde(rename = "playerId")]
+    player_id: i32,
+    role: Role,
+}
+
+#[post("/set_role")]
+#[instrument]
+pub async fn set_role(admin: AdminPlayer, info: web::Query<RoleInfo>) -> HttpResult {
+    let info = info.into_inner();
+    admin.set_role(info.player_id, info.role)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/disputed_kills")]
+#[instrument]
+pub async fn get_disputed_kills(admin: AdminPlayer, info: web::Query<GameInfo>) -> HttpResult {
+    let disputed_kills = admin.disputed_kills(&info.game_code)?;
+    Ok(HttpResponse::Ok().json(disputed_kills))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveKillInfo {
+    #[serde(rename = "gameCode")]
+    game_code: String,
+    #[serde(rename = "claimId")]
+    claim_id: i32,
+    confirm: bool,
+}
+
+#[post("/resolve_kill")]
+#[instrument]
+pub async fn resolve_kill(admin: AdminPlayer, info: web::Query<ResolveKillInfo>) -> HttpResult {
+    admin.resolve_kill(&info.game_code, info.claim_id, info.confirm)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .service(list_players)
+            .service(list_games)
+            .service(list_actions)
+            .service(end_game)
+            .service(pause)
+            .service(resume)
+            .service(kick)
+            .service(set_role)
+            .service(get_disputed_kills)
+            .service(resolve_kill),
+    );
+}
@@ -0,0 +1,49 @@
+use actix_web::{post, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::models::api_errors::ApiError;
+use crate::utils::auth::DevProvider;
+use crate::utils::config::{AuthProvider, CFG};
+
+type HttpResult = std::result::Result<HttpResponse, ApiError>;
+
+#[derive(Debug, Deserialize)]
+pub struct MintInfo {
+    uid: String,
+    email: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MintedToken {
+    token: String,
+}
+
+/// Issues a token for any user, for local development and integration tests
+#[post("/dev/token")]
+#[instrument]
+pub async fn mint_token(info: web::Json<MintInfo>) -> HttpResult {
+    let secret = CFG
+        .dev_auth_secret
+        .as_ref()
+        .ok_or_else(|| ApiError::InternalServerError("DEV_AUTH_DISABLED".to_string()))?;
+
+    let email = info
+        .email
+        .clone()
+        .unwrap_or_else(|| format!("{}@assassin.dev", info.uid));
+
+    let token = DevProvider::new(secret)
+        .mint_token(&info.uid, &email)
+        .map_err(|_| ApiError::InternalServerError("TOKEN_SIGNING_FAILED".to_string()))?;
+
+    info!("Minted a dev token for {}", info.uid);
+    Ok(HttpResponse::Ok().json(MintedToken { token }))
+}
+
+/// Only does something when the dev auth provider is in use
+pub fn config(cfg: &mut web::ServiceConfig) {
+    if CFG.auth_provider == AuthProvider::Dev {
+        cfg.service(mint_token);
+    }
+}
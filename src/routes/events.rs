@@ -0,0 +1,216 @@
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{self, CloseCode, CloseReason, Codec, Frame, Message};
+use actix_web::http::header;
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{get, rt, web, Error, HttpRequest, HttpResponse};
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::{future, stream, Stream, StreamExt};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tracing::{info, instrument};
+
+use crate::models::api_errors::ApiError;
+use crate::models::constants::DEFAULT_PAGE_SIZE;
+use crate::models::events;
+use crate::models::game::Game;
+use crate::models::player::Player;
+
+type HttpResult = std::result::Result<HttpResponse, ApiError>;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Pushes the events of a game to one of its members for as long as the socket stays open
+#[get("/games/{code}/ws")]
+#[instrument(skip(req, payload))]
+pub async fn game_events_ws(
+    player: Player,
+    code: web::Path<String>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let game = Game::find_as_member(&code, player.id).map_err(ApiError::from)?;
+    let mut response = ws::handshake(req.head())?;
+
+    // The subscription ends when the player is out of the game, and so does the socket
+    let events = events::subscribe(game.id, player.id)
+        .map(|event| {
+            Message::Text(serde_json::to_string(&event).expect("game events always serialize"))
+        })
+        .chain(stream::once(future::ready(Message::Close(Some(
+            CloseCode::Normal.into(),
+        )))));
+
+    let last_seen = Rc::new(Cell::new(Instant::now()));
+    let (replies, replies_rx) = unbounded();
+    rt::spawn(read_frames(payload, replies, last_seen.clone()));
+
+    info!("Player {} is listening to game {}", player.id, game.code);
+
+    let outgoing = stream::select(stream::select(events, replies_rx), heartbeat(last_seen)).scan(
+        false,
+        |closed, msg| {
+            // Nothing may follow a close frame
+            if *closed {
+                return future::ready(None);
+            }
+            *closed = matches!(msg, Message::Close(_));
+            future::ready(Some(msg))
+        },
+    );
+
+    let mut codec = Codec::new();
+    Ok(response.streaming(outgoing.map(move |msg| {
+        let mut buf = BytesMut::new();
+        codec.encode(msg, &mut buf)?;
+        Ok::<Bytes, Error>(buf.freeze())
+    })))
+}
+
+/// Answers the client's pings and notices when it goes away
+async fn read_frames(
+    mut payload: web::Payload,
+    replies: UnboundedSender<Message>,
+    last_seen: Rc<Cell<Instant>>,
+) {
+    let mut codec = Codec::new();
+    let mut buf = BytesMut::new();
+
+    while let Some(Ok(chunk)) = payload.next().await {
+        buf.extend_from_slice(&chunk);
+        last_seen.set(Instant::now());
+
+        loop {
+            let reply = match codec.decode(&mut buf) {
+                Ok(Some(Frame::Ping(msg))) => Message::Pong(msg),
+                Ok(Some(Frame::Close(reason))) => Message::Close(reason),
+                Ok(Some(_)) => continue, // The stream is one way, anything else is ignored
+                Ok(None) => break,
+                Err(_) => Message::Close(Some(CloseCode::Protocol.into())),
+            };
+
+            let is_close = matches!(reply, Message::Close(_));
+            if replies.unbounded_send(reply).is_err() || is_close {
+                return;
+            }
+        }
+    }
+
+    let _ = replies.unbounded_send(Message::Close(None));
+}
+
+/// Pings the client regularly, and hangs up on it if it stopped answering
+fn heartbeat(last_seen: Rc<Cell<Instant>>) -> impl Stream<Item = Message> {
+    ticks().map(move |_| {
+        if last_seen.get().elapsed() > CLIENT_TIMEOUT {
+            Message::Close(Some(CloseReason {
+                code: CloseCode::Away,
+                description: Some("Heartbeat timeout".to_string()),
+            }))
+        } else {
+            Message::Ping(Bytes::new())
+        }
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventPageInfo {
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+/// The game's log, one page at a time. Clients asking for `text/event-stream` get
+/// the events streamed as they happen instead.
+#[get("/games/{code}/events")]
+#[instrument(skip(req))]
+pub async fn game_events(
+    player: Player,
+    code: web::Path<String>,
+    info: web::Query<EventPageInfo>,
+    req: HttpRequest,
+) -> HttpResult {
+    let wants_stream = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .filter(|accept| accept.contains("text/event-stream"))
+        .is_some();
+
+    if wants_stream {
+        return stream_events(player, &code, &req);
+    }
+
+    let page = events::page(
+        &code,
+        &player,
+        info.after.unwrap_or(0),
+        info.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// Server-sent events, for the clients which can't keep a WebSocket open. Whatever was missed
+/// since the event given in `Last-Event-ID` is replayed from the log before going live. Live
+/// events are the ones Postgres announces, so they come from every replica like the replay does.
+fn stream_events(player: Player, code: &String, req: &HttpRequest) -> HttpResult {
+    let game = Game::find_as_member(code, player.id)?;
+
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| id.parse::<i32>().ok())
+        .unwrap_or(0);
+
+    // Subscribe before reading the log, so that no event committed in between can slip through
+    let live = events::subscribe(game.id, player.id);
+    let missed = events::logged_since(&game, player.id, last_event_id)?;
+
+    let replayed: HashSet<i32> = missed.iter().map(|event| event.id).collect();
+    let live = live.filter(move |event| future::ready(!replayed.contains(&event.id)));
+
+    info!(
+        "Player {} is listening to game {} from event {}",
+        player.id, game.code, last_event_id
+    );
+
+    // The live events end when the player is out of the game, which is marked by a `None`
+    let frames = stream::iter(missed)
+        .chain(live)
+        .map(|event| {
+            let data = serde_json::to_string(&event).expect("game events always serialize");
+            Some(Bytes::from(format!("id: {}\ndata: {}\n\n", event.id, data)))
+        })
+        .chain(stream::once(future::ready(None)));
+    // Comments keep the proxies from timing the connection out
+    let keep_alive = ticks().map(|_| Some(Bytes::from_static(b": keep-alive\n\n")));
+
+    let body = stream::select(frames, keep_alive)
+        .take_while(|frame| future::ready(frame.is_some()))
+        .filter_map(future::ready)
+        .map(Ok::<Bytes, ApiError>);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .streaming(body))
+}
+
+fn ticks() -> impl Stream<Item = ()> {
+    let ticks = stream::unfold(
+        rt::time::interval(HEARTBEAT_INTERVAL),
+        |mut interval| async {
+            interval.tick().await;
+            Some(((), interval))
+        },
+    );
+
+    Box::pin(ticks)
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(game_events_ws).service(game_events);
+}
@@ -3,22 +3,35 @@ use serde::{Deserialize, Serialize};
 use tracing::{info, instrument};
 
 use crate::models::api_errors::ApiError;
-use crate::models::enums::GameStatus;
+use crate::models::constants;
 use crate::models::game::Game;
+use crate::models::model_errors::ModelError;
 use crate::models::player::Player;
+use crate::models::settings::GameSettings;
 
 type HttpResult = std::result::Result<HttpResponse, ApiError>;
 
 #[derive(Debug, Deserialize)]
 pub struct GameCreationInfo {
     game_name: String,
-    max_players: Option<i32>,
+    max_players: Option<i32>, //Kept for older clients, can't be sent along with `settings`
+    settings: Option<GameSettings>,
 }
 
 #[post("/create_game")]
 #[instrument]
 pub async fn create(player: Player, info: web::Json<GameCreationInfo>) -> HttpResult {
-    let game = Game::new(info.game_name.clone(), player.id, info.max_players)?;
+    let info = info.into_inner();
+    let settings = match (info.settings, info.max_players) {
+        // The settings always carry a player limit, which the old field would contradict
+        (Some(_), Some(_)) => return Err(ModelError::InvalidSettings.into()),
+        (Some(settings), None) => settings,
+        (None, max_players) => GameSettings {
+            max_players: max_players.unwrap_or(constants::DEFAULT_MAX_PLAYERS),
+            ..GameSettings::default()
+        },
+    };
+    let game = Game::new(info.game_name, player.id, settings)?;
     info!("Succesfully created game {}", game.code);
     Ok(HttpResponse::Created().json(GameInfo {
         game_code: game.code,
@@ -38,6 +51,39 @@ pub async fn join(player: Player, info: web::Query<GameInfo>) -> HttpResult {
     Ok(HttpResponse::Ok().finish())
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LobbySearchInfo {
+    search: Option<String>,
+    #[serde(rename = "minSize")]
+    min_size: Option<i32>,
+    #[serde(rename = "maxSize")]
+    max_size: Option<i32>,
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+/// Public games which can be joined right away. Sizes filter on the maximum number of players.
+#[get("/lobbies")]
+#[instrument]
+pub async fn get_lobbies(player: Player, info: web::Query<LobbySearchInfo>) -> HttpResult {
+    let page = Game::lobbies(
+        player.id,
+        info.search.as_deref(),
+        info.min_size,
+        info.max_size,
+        info.after.unwrap_or(0),
+        info.limit.unwrap_or(constants::DEFAULT_PAGE_SIZE),
+    )?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[post("/leave_game")]
+#[instrument]
+pub async fn leave(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    Game::leave_game(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 #[post("/start_game")]
 #[instrument]
 pub async fn start(player: Player, info: web::Query<GameInfo>) -> HttpResult {
@@ -45,19 +91,26 @@ pub async fn start(player: Player, info: web::Query<GameInfo>) -> HttpResult {
     Ok(HttpResponse::Ok().finish())
 }
 
-#[derive(Serialize)]
-pub struct StatusResult {
-    game_status: GameStatus,
-}
-
 //TODO: only people inside the lobby should be able to query this
 #[get("/game_status")]
 #[instrument]
 pub async fn get_status(player: Player, info: web::Query<GameInfo>) -> HttpResult {
     let status = Game::get_game_status(&info.game_code)?;
-    Ok(HttpResponse::Ok().json(StatusResult {
-        game_status: status,
-    }))
+    Ok(HttpResponse::Ok().json(status))
+}
+
+#[post("/pause_game")]
+#[instrument]
+pub async fn pause(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    Game::pause_game(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/resume_game")]
+#[instrument]
+pub async fn resume(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    Game::resume_game(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().finish())
 }
 
 #[get("/agent_info")]
@@ -74,6 +127,83 @@ pub async fn kill(player: Player, info: web::Query<GameInfo>) -> HttpResult {
     Ok(HttpResponse::Ok().finish())
 }
 
+#[post("/confirm_kill")]
+#[instrument]
+pub async fn confirm_kill(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    Game::confirm_kill(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/dispute_kill")]
+#[instrument]
+pub async fn dispute_kill(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    Game::dispute_kill(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/disputed_kills")]
+#[instrument]
+pub async fn get_disputed_kills(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    let disputed_kills = Game::get_disputed_kills(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().json(disputed_kills))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveKillInfo {
+    #[serde(rename = "gameCode")]
+    game_code: String,
+    #[serde(rename = "claimId")]
+    claim_id: i32,
+    confirm: bool,
+}
+
+#[post("/resolve_kill")]
+#[instrument]
+pub async fn resolve_kill(player: Player, info: web::Query<ResolveKillInfo>) -> HttpResult {
+    Game::resolve_disputed_kill(&info.game_code, player.id, info.claim_id, info.confirm)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModerationInfo {
+    #[serde(rename = "gameCode")]
+    game_code: String,
+    #[serde(rename = "playerId")]
+    player_id: i32,
+}
+
+#[post("/kick_player")]
+#[instrument]
+pub async fn kick_player(player: Player, info: web::Query<ModerationInfo>) -> HttpResult {
+    Game::kick_player(&info.game_code, player.id, info.player_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/ban_player")]
+#[instrument]
+pub async fn ban_player(player: Player, info: web::Query<ModerationInfo>) -> HttpResult {
+    Game::ban_player(&info.game_code, player.id, info.player_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/transfer_ownership")]
+#[instrument]
+pub async fn transfer_ownership(player: Player, info: web::Query<ModerationInfo>) -> HttpResult {
+    Game::transfer_ownership(&info.game_code, player.id, info.player_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/game_settings")]
+#[instrument]
+pub async fn update_settings(
+    player: Player,
+    info: web::Query<GameInfo>,
+    settings: web::Json<GameSettings>,
+) -> HttpResult {
+    Game::update_settings(&info.game_code, player.id, settings.into_inner())?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 #[get("/game_info")]
 #[instrument]
 pub async fn get_game_info(player: Player, info: web::Query<GameInfo>) -> HttpResult {
@@ -81,6 +211,13 @@ pub async fn get_game_info(player: Player, info: web::Query<GameInfo>) -> HttpRe
     Ok(HttpResponse::Ok().json(game_info))
 }
 
+#[get("/game_stats")]
+#[instrument]
+pub async fn get_game_stats(player: Player, info: web::Query<GameInfo>) -> HttpResult {
+    let game_stats = Game::game_stats(&info.game_code, player.id)?;
+    Ok(HttpResponse::Ok().json(game_stats))
+}
+
 #[get("/user_info")]
 #[instrument]
 pub async fn get_user_info(player: Player) -> HttpResult {
@@ -112,11 +249,24 @@ pub async fn end_game(player: Player, info: web::Query<GameInfo>) -> HttpResult
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(create)
         .service(join)
+        .service(get_lobbies)
+        .service(leave)
         .service(start)
         .service(get_status)
+        .service(pause)
+        .service(resume)
         .service(get_agent_info)
         .service(kill)
+        .service(confirm_kill)
+        .service(dispute_kill)
+        .service(kick_player)
+        .service(ban_player)
+        .service(transfer_ownership)
+        .service(get_disputed_kills)
+        .service(resolve_kill)
+        .service(update_settings)
         .service(get_game_info)
+        .service(get_game_stats)
         .service(get_user_info)
         .service(get_codenames)
         .service(get_end_time)
@@ -0,0 +1,59 @@
+use actix_web::{get, web, HttpResponse};
+use serde::Deserialize;
+use tracing::instrument;
+
+use crate::models::api_errors::ApiError;
+use crate::models::constants::DEFAULT_PAGE_SIZE;
+use crate::models::leaderboard::{self, RankingMetric};
+use crate::models::player::Player;
+
+type HttpResult = std::result::Result<HttpResponse, ApiError>;
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardInfo {
+    season: Option<String>, // All-time when missing
+    #[serde(default)]
+    by: RankingMetric,
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[get("/leaderboard")]
+#[instrument]
+pub async fn get_leaderboard(_player: Player, info: web::Query<LeaderboardInfo>) -> HttpResult {
+    let page = leaderboard::page(
+        info.season.as_deref(),
+        info.by,
+        info.after.unwrap_or(0),
+        info.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RankInfo {
+    season: Option<String>,
+    #[serde(default)]
+    by: RankingMetric,
+}
+
+/// Standing of the player, or `null` if they didn't finish any game in the season
+#[get("/leaderboard/me")]
+#[instrument]
+pub async fn get_own_rank(player: Player, info: web::Query<RankInfo>) -> HttpResult {
+    let entry = leaderboard::rank_of(player.id, info.season.as_deref(), info.by)?;
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+#[get("/leaderboard/seasons")]
+#[instrument]
+pub async fn get_seasons(_player: Player) -> HttpResult {
+    let seasons = leaderboard::seasons()?;
+    Ok(HttpResponse::Ok().json(seasons))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_leaderboard)
+        .service(get_own_rank)
+        .service(get_seasons);
+}
@@ -0,0 +1,35 @@
+use actix_web::http::header;
+use actix_web::{get, web, HttpResponse};
+use tracing::{error, instrument};
+
+use crate::models::api_errors::ApiError;
+use crate::utils::storage::{is_valid_name, STORAGE};
+
+type HttpResult = std::result::Result<HttpResponse, ApiError>;
+
+/// Serves the files kept by the local storage backend, such as profile pictures.
+/// Their names never get reused, so clients may cache them forever.
+#[get("/media/{name}")]
+#[instrument]
+pub async fn get_file(name: web::Path<String>) -> HttpResult {
+    if !is_valid_name(&name) {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let content = STORAGE.get(&name).map_err(|e| {
+        error!("Could not read media file {}: {:?}", name, e);
+        ApiError::InternalServerError("UNKNOWN".to_string())
+    })?;
+
+    match content {
+        Some(content) => Ok(HttpResponse::Ok()
+            .content_type("image/jpeg")
+            .set_header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+            .body(content)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_file);
+}
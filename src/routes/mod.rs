@@ -1,4 +1,10 @@
+pub mod admin;
 pub mod auth;
 pub mod debug;
+pub mod dev;
+pub mod events;
 pub mod game;
 pub mod health;
+pub mod leaderboard;
+pub mod media;
+pub mod profile;
@@ -0,0 +1,132 @@
+use actix_web::error::BlockingError;
+use actix_web::http::header;
+use actix_web::{delete, get, put, web, HttpRequest, HttpResponse};
+use multipart::server::Multipart;
+use serde::Deserialize;
+use std::io::{Cursor, Read};
+use tracing::{info, instrument};
+
+use crate::models::api_errors::ApiError;
+use crate::models::constants::DEFAULT_PAGE_SIZE;
+use crate::models::model_errors::ModelError;
+use crate::models::player::Player;
+use crate::models::stats::CareerStats;
+use crate::utils::pictures::MAX_UPLOAD_BYTES;
+
+type HttpResult = std::result::Result<HttpResponse, ApiError>;
+
+/// Name of the multipart field holding the picture
+const PICTURE_FIELD: &str = "picture";
+/// Room for the multipart headers around the picture itself
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryPageInfo {
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NicknameInfo {
+    nickname: String,
+}
+
+#[put("/me/nickname")]
+#[instrument]
+pub async fn update_nickname(player: Player, info: web::Json<NicknameInfo>) -> HttpResult {
+    player.update_nickname(&info.nickname)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// The games the player took part in, latest first
+#[get("/me/games")]
+#[instrument]
+pub async fn get_game_history(player: Player, info: web::Query<HistoryPageInfo>) -> HttpResult {
+    let page = player.game_history(info.after, info.limit.unwrap_or(DEFAULT_PAGE_SIZE))?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[get("/players/{id}/stats")]
+#[instrument]
+pub async fn get_player_stats(player: Player, id: web::Path<i32>) -> HttpResult {
+    let stats = CareerStats::of(id.into_inner())?;
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Anonymizes the account of the player, who gets taken out of their current game
+#[delete("/me")]
+#[instrument]
+pub async fn delete_account(player: Player) -> HttpResult {
+    player.delete_account()?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/me/export")]
+#[instrument]
+pub async fn export_data(player: Player) -> HttpResult {
+    let export = player.export_data()?;
+    Ok(HttpResponse::Ok()
+        .set_header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"assassin-export.json\"",
+        )
+        .json(export))
+}
+
+/// Expects a `multipart/form-data` body with the image in its `picture` field
+#[instrument(skip(req, body))]
+pub async fn upload_picture(player: Player, req: HttpRequest, body: web::Bytes) -> HttpResult {
+    let upload = picture_from_multipart(&req, &body).ok_or(ModelError::InvalidPicture)?;
+
+    let player_id = player.id;
+    let picture = web::block(move || player.update_picture(&upload))
+        .await
+        .map_err(|e| match e {
+            BlockingError::Error(e) => ApiError::from(e),
+            BlockingError::Canceled => ApiError::InternalServerError("UNKNOWN".to_string()),
+        })?;
+
+    info!("User {} uploaded a new picture", player_id);
+    Ok(HttpResponse::Ok().json(picture))
+}
+
+/// Pulls the picture out of the multipart body, if there is one
+fn picture_from_multipart(req: &HttpRequest, body: &[u8]) -> Option<Vec<u8>> {
+    let content_type = req.headers().get(header::CONTENT_TYPE)?.to_str().ok()?;
+
+    let boundary = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))?
+        .trim_matches('"');
+
+    let mut multipart = Multipart::with_body(Cursor::new(body), boundary);
+
+    while let Ok(Some(field)) = multipart.read_entry() {
+        if &*field.headers.name == PICTURE_FIELD {
+            let mut upload = Vec::new();
+            field
+                .data
+                .take(MAX_UPLOAD_BYTES as u64)
+                .read_to_end(&mut upload)
+                .ok()?;
+            return Some(upload);
+        }
+    }
+
+    None
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(update_nickname)
+        .service(get_game_history)
+        .service(get_player_stats)
+        .service(delete_account)
+        .service(export_data)
+        .service(
+            web::resource("/me/picture")
+                .app_data(web::PayloadConfig::new(
+                    MAX_UPLOAD_BYTES + MULTIPART_OVERHEAD,
+                ))
+                .route(web::put().to(upload_picture)),
+        );
+}
@@ -1,3 +1,18 @@
+table! {
+    use diesel::sql_types::*;
+    use crate::models::enums::*;
+
+    admin_action (id) {
+        id -> Int4,
+        admin -> Int4,
+        kind -> Admin_action_kind_t,
+        game -> Nullable<Int4>,
+        player -> Nullable<Int4>,
+        details -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
     use crate::models::enums::*;
@@ -26,6 +41,89 @@ table! {
         created_at -> Timestamptz,
         start_time -> Nullable<Timestamptz>,
         end_time -> Nullable<Timestamptz>,
+        winner -> Nullable<Int4>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+
+    game_ban (game, player) {
+        game -> Int4,
+        player -> Int4,
+        banned_by -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::models::enums::*;
+
+    game_event (id) {
+        id -> Int4,
+        game -> Int4,
+        recipient -> Nullable<Int4>,
+        created_at -> Timestamptz,
+        kind -> Game_event_kind_t,
+        actor -> Nullable<Int4>,
+        subject -> Nullable<Int4>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+
+    game_pause (id) {
+        id -> Int4,
+        game -> Int4,
+        start_time -> Timestamptz,
+        end_time -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::models::enums::*;
+
+    game_settings (game) {
+        game -> Int4,
+        duration_minutes -> Int4,
+        min_players -> Int4,
+        allow_late_join -> Bool,
+        kill_confirmation -> Kill_confirmation_t,
+        score_per_kill -> Int4,
+        score_for_surviving -> Int4,
+        score_for_winning -> Int4,
+        is_public -> Bool,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::models::enums::*;
+
+    kill_claim (id) {
+        id -> Int4,
+        game -> Int4,
+        assassin -> Int4,
+        target -> Int4,
+        status -> Kill_claim_status_t,
+        created_at -> Timestamptz,
+        resolved_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+
+    leaderboard_entry (season, player) {
+        season -> Varchar,
+        player -> Int4,
+        games_played -> Int4,
+        wins -> Int4,
+        kills -> Int4,
+        rating -> Int4,
     }
 }
 
@@ -41,6 +139,8 @@ table! {
         role -> Role_t,
         picture -> Nullable<Varchar>,
         registered_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
+        rating -> Int4,
     }
 }
 
@@ -57,14 +157,43 @@ table! {
     }
 }
 
+table! {
+    use diesel::sql_types::*;
+
+    rating_history (player, game) {
+        player -> Int4,
+        game -> Int4,
+        rating_before -> Int4,
+        rating_after -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+joinable!(admin_action -> game (game));
 joinable!(assignment -> game (game));
 joinable!(game -> player (owner));
+joinable!(game_ban -> game (game));
+joinable!(game_event -> game (game));
+joinable!(game_pause -> game (game));
+joinable!(game_settings -> game (game));
+joinable!(kill_claim -> game (game));
+joinable!(leaderboard_entry -> player (player));
 joinable!(playergame -> game (game));
 joinable!(playergame -> player (player));
+joinable!(rating_history -> game (game));
+joinable!(rating_history -> player (player));
 
 allow_tables_to_appear_in_same_query!(
+    admin_action,
     assignment,
     game,
+    game_ban,
+    game_event,
+    game_pause,
+    game_settings,
+    kill_claim,
+    leaderboard_entry,
     player,
     playergame,
+    rating_history,
 );
@@ -0,0 +1,60 @@
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use thiserror::Error;
+use tracing::info;
+
+pub type AuthResult<T> = std::result::Result<T, AuthError>;
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("The token could not be decoded")]
+    MalformedToken,
+    #[error("The token has expired")]
+    ExpiredToken,
+    #[error("The token is not valid yet")]
+    ImmatureToken,
+    #[error("The token was signed with an unknown key")]
+    UnknownKid(String),
+    #[error("The token signature is not valid")]
+    InvalidSignature,
+    #[error("The token was not issued by the expected issuer")]
+    InvalidIssuer,
+    #[error("The token was issued for another audience")]
+    InvalidAudience,
+    #[error("The token carries no email")]
+    MissingEmail,
+    #[error("The email of the user is not verified")]
+    EmailNotVerified,
+    #[error("Could not authenticate the request")]
+    Internal,
+}
+
+impl AuthError {
+    pub fn error_code(&self) -> String {
+        match self {
+            Self::MalformedToken => "MALFORMED_TOKEN".to_string(),
+            Self::ExpiredToken => "EXPIRED_TOKEN".to_string(),
+            Self::ImmatureToken => "IMMATURE_TOKEN".to_string(),
+            Self::UnknownKid(_) => "UNKNOWN_KID".to_string(),
+            Self::InvalidSignature => "INVALID_SIGNATURE".to_string(),
+            Self::InvalidIssuer => "INVALID_ISSUER".to_string(),
+            Self::InvalidAudience => "INVALID_AUDIENCE".to_string(),
+            Self::MissingEmail => "MISSING_EMAIL".to_string(),
+            Self::EmailNotVerified => "EMAIL_NOT_VERIFIED".to_string(),
+            Self::Internal => "AUTH_INTERNAL_ERROR".to_string(),
+        }
+    }
+}
+
+impl From<JwtError> for AuthError {
+    fn from(err: JwtError) -> Self {
+        info!("Token validation failed: {:?}", err);
+        match err.kind() {
+            ErrorKind::ExpiredSignature => Self::ExpiredToken,
+            ErrorKind::ImmatureSignature => Self::ImmatureToken,
+            ErrorKind::InvalidIssuer => Self::InvalidIssuer,
+            ErrorKind::InvalidAudience => Self::InvalidAudience,
+            ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm => Self::InvalidSignature,
+            _ => Self::MalformedToken,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use color_eyre::{eyre::eyre, eyre::WrapErr, Result};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use openssl::x509::X509;
+use serde::Deserialize;
+use serde_json::Value;
+use snailquote;
+
+use super::keys::{self, FetchedKeys, KeyCache, Keys};
+use super::{verify, AuthResult, IdentityProvider, UserClaims};
+
+const ISSUER_PREFIX: &str = "https://securetoken.google.com/";
+const GOOGLE_PK_URL: &str =
+    "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com";
+
+/// The part of Firebase's ID tokens we care about
+#[derive(Debug, Deserialize)]
+struct FirebaseClaims {
+    user_id: String,
+    email: String,
+    email_verified: bool,
+}
+
+/// Validates the ID tokens issued by Firebase Authentication for the given project
+pub struct FirebaseProvider {
+    validation: Validation,
+    validation_keys: KeyCache,
+}
+
+impl FirebaseProvider {
+    pub fn new(project_id: &str) -> FirebaseProvider {
+        let validation = get_validator(project_id);
+        let validation_keys = KeyCache::new(get_validation_keys);
+        FirebaseProvider {
+            validation,
+            validation_keys,
+        }
+    }
+}
+
+impl IdentityProvider for FirebaseProvider {
+    fn validate_token(&self, id_token: &str) -> AuthResult<UserClaims> {
+        let claims: FirebaseClaims = verify(id_token, &self.validation_keys, &self.validation)?;
+
+        Ok(UserClaims {
+            user_id: claims.user_id,
+            email: claims.email,
+            email_verified: claims.email_verified,
+        })
+    }
+
+    fn key_cache(&self) -> Option<&KeyCache> {
+        Some(&self.validation_keys)
+    }
+}
+
+fn get_validator(project_id: &str) -> Validation {
+    let mut validator = Validation {
+        iss: Some(format!("{}{}", ISSUER_PREFIX, project_id)),
+        algorithms: vec![Algorithm::RS256],
+        ..Validation::default()
+    };
+
+    validator.set_audience(&[project_id]);
+    validator
+}
+
+fn get_validation_keys() -> Result<FetchedKeys> {
+    let (body, max_age) = keys::fetch(GOOGLE_PK_URL)?;
+
+    let certs: Value =
+        serde_json::from_str(&body).wrap_err("Google's public keys are not valid JSON")?;
+
+    let keys = certs
+        .as_object()
+        .ok_or(eyre!("Google's public keys are not a JSON object"))?
+        .iter()
+        .map(|(fingerprint, cert)| {
+            Ok((
+                fingerprint.clone(),
+                get_key_from_certificate(cert.to_string())?,
+            ))
+        })
+        .collect::<Result<Keys>>()?;
+
+    Ok(FetchedKeys { keys, max_age })
+}
+
+fn get_key_from_certificate(certificate: String) -> Result<DecodingKey<'static>> {
+    let certificate = snailquote::unescape(&certificate)?;
+    let cert = X509::from_pem(certificate.as_bytes())
+        .wrap_err("Could not decode RSA Verification key from Google")?;
+    let key = cert.public_key()?.public_key_to_pem()?;
+
+    Ok(DecodingKey::from_rsa_pem(&key)?.into_static())
+}
@@ -0,0 +1,155 @@
+use actix_web::web;
+use color_eyre::{eyre::WrapErr, Result};
+use jsonwebtoken::DecodingKey;
+use reqwest;
+use reqwest::header::CACHE_CONTROL;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use super::{AuthError, AuthResult};
+
+/// Lifetime of the keys when the provider doesn't tell
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+/// Never refresh more often than this, whatever the provider says
+const MIN_MAX_AGE: Duration = Duration::from_secs(60);
+/// How long to wait before trying again after a failed fetch
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Tokens signed with unknown keys can't make us fetch more often than this
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+pub type Keys = HashMap<String, DecodingKey<'static>>;
+
+type Fetcher = Box<dyn Fn() -> Result<FetchedKeys> + Send + Sync>;
+
+pub struct FetchedKeys {
+    pub keys: Keys,
+    pub max_age: Option<Duration>,
+}
+
+struct RefreshState {
+    next_refresh: Instant,
+    last_fetch: Option<Instant>,
+}
+
+/// Signing keys of an identity provider. They get swapped for fresh ones whenever
+/// the provider rotates them, and the last good ones are kept if fetching fails.
+pub struct KeyCache {
+    fetch: Fetcher,
+    keys: RwLock<Keys>,
+    state: Mutex<RefreshState>,
+}
+
+impl KeyCache {
+    /// Fetches the keys right away. If that fails, the cache starts empty and will try again soon.
+    pub fn new(fetch: impl Fn() -> Result<FetchedKeys> + Send + Sync + 'static) -> KeyCache {
+        let cache = KeyCache {
+            fetch: Box::new(fetch),
+            keys: RwLock::new(HashMap::new()),
+            state: Mutex::new(RefreshState {
+                next_refresh: Instant::now(),
+                last_fetch: None,
+            }),
+        };
+
+        cache.refresh_blocking();
+        cache
+    }
+
+    pub fn get(&self, kid: &str) -> AuthResult<DecodingKey<'static>> {
+        self.keys
+            .read()
+            .unwrap()
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| AuthError::UnknownKid(kid.to_string()))
+    }
+
+    /// Time left until the keys should be refreshed
+    pub fn next_refresh_in(&self) -> Duration {
+        let next_refresh = self.state.lock().unwrap().next_refresh;
+        next_refresh.saturating_duration_since(Instant::now())
+    }
+
+    /// Fetches the keys again, off the async runtime. Returns whether it succeeded.
+    pub async fn refresh(&'static self) -> bool {
+        web::block(move || Ok::<_, ()>(self.refresh_blocking()))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Called when a token was signed with a key we don't know, which is what happens right
+    /// after the provider rotated its keys. Fetches are rate-limited, since anybody can send
+    /// us such tokens. Returns whether the keys were fetched again.
+    pub async fn refetch_unknown(&'static self) -> bool {
+        let allowed = {
+            let mut state = self.state.lock().unwrap();
+            let allowed = match state.last_fetch {
+                Some(last) => last.elapsed() >= MIN_REFETCH_INTERVAL,
+                None => true,
+            };
+            if allowed {
+                state.last_fetch = Some(Instant::now());
+            }
+            allowed
+        };
+
+        allowed && self.refresh().await
+    }
+
+    fn refresh_blocking(&self) -> bool {
+        self.state.lock().unwrap().last_fetch = Some(Instant::now());
+
+        match (self.fetch)() {
+            Ok(fetched) => {
+                let max_age = fetched.max_age.unwrap_or(DEFAULT_MAX_AGE).max(MIN_MAX_AGE);
+
+                info!(
+                    "Fetched {} signing keys, next refresh in {}s",
+                    fetched.keys.len(),
+                    max_age.as_secs()
+                );
+
+                *self.keys.write().unwrap() = fetched.keys;
+                self.state.lock().unwrap().next_refresh = Instant::now() + max_age;
+                true
+            }
+            Err(e) => {
+                error!(
+                    "Could not fetch signing keys, keeping the old ones: {:?}",
+                    e
+                );
+                self.state.lock().unwrap().next_refresh = Instant::now() + RETRY_DELAY;
+                false
+            }
+        }
+    }
+}
+
+/// Fetches the given URL, along with how long its content may be cached
+pub fn fetch(url: &str) -> Result<(String, Option<Duration>)> {
+    let res = reqwest::blocking::get(url)
+        .and_then(|res| res.error_for_status())
+        .wrap_err_with(|| format!("Could not fetch {}", url))?;
+
+    let max_age = res
+        .headers()
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_max_age);
+
+    let body = res
+        .text()
+        .wrap_err_with(|| format!("Could not read response from {}", url))?;
+
+    Ok((body, max_age))
+}
+
+fn parse_max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.trim_matches('"').parse().ok())
+        .map(Duration::from_secs)
+}
@@ -0,0 +1,138 @@
+use lazy_static;
+
+use actix_web::{dev::Payload, dev::ServiceRequest, Error, FromRequest, HttpRequest};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use futures_util::future::{err, ok, Ready};
+use jsonwebtoken::{decode, decode_header, Validation};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::models::api_errors::ApiError;
+use crate::utils::config::{AuthProvider, CFG};
+
+mod dev;
+mod errors;
+mod firebase;
+mod keys;
+mod oidc;
+
+pub use dev::DevProvider;
+pub use errors::{AuthError, AuthResult};
+pub use firebase::FirebaseProvider;
+pub use keys::KeyCache;
+pub use oidc::OidcProvider;
+
+lazy_static::lazy_static! {
+    pub static ref VALIDATOR: Box<dyn IdentityProvider> = provider_from_config();
+}
+
+/// Identity of the bearer of a token, whichever provider issued it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserClaims {
+    pub user_id: String,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+impl FromRequest for UserClaims {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let mut ext = req.extensions_mut();
+        let claims = ext.remove::<UserClaims>();
+        match claims {
+            Some(claims) => ok(claims),
+            // This should never happen anyways
+            None => err(ApiError::Unauthorized("MISSING_CLAIMS".to_string())),
+        }
+    }
+}
+
+/// Something able to vouch for the identity of whoever sends us a token
+pub trait IdentityProvider: Send + Sync {
+    fn validate_token(&self, id_token: &str) -> AuthResult<UserClaims>;
+
+    /// Keys the provider signs its tokens with, if they need to be fetched
+    fn key_cache(&self) -> Option<&KeyCache>;
+}
+
+fn provider_from_config() -> Box<dyn IdentityProvider> {
+    match CFG.auth_provider {
+        AuthProvider::Firebase => Box::new(FirebaseProvider::new(&CFG.firebase_project_id)),
+        AuthProvider::Oidc => {
+            let issuer = CFG
+                .oidc_issuer
+                .as_ref()
+                .expect("OIDC_ISSUER must be set to use the OIDC provider");
+            let audience = CFG
+                .oidc_audience
+                .as_ref()
+                .expect("OIDC_AUDIENCE must be set to use the OIDC provider");
+
+            Box::new(OidcProvider::new(issuer, audience).expect("Could not set up OIDC provider"))
+        }
+        AuthProvider::Dev => {
+            let secret = CFG
+                .dev_auth_secret
+                .as_ref()
+                .expect("DEV_AUTH_SECRET must be set to use the dev provider");
+
+            warn!("Using the dev auth provider: anybody can mint tokens!");
+            Box::new(DevProvider::new(secret))
+        }
+    }
+}
+
+/// Verifies the token against the key it was signed with, out of the given ones
+fn verify<T: DeserializeOwned>(
+    id_token: &str,
+    keys: &KeyCache,
+    validation: &Validation,
+) -> AuthResult<T> {
+    let kid = decode_header(id_token)?
+        .kid
+        .ok_or(AuthError::MalformedToken)?;
+
+    let validation_key = keys.get(&kid)?;
+
+    Ok(decode::<T>(id_token, &validation_key, validation)?.claims)
+}
+
+/// Validates the token with the configured provider, and makes sure the user can be trusted
+async fn authenticate(id_token: &str) -> AuthResult<UserClaims> {
+    let claims = match VALIDATOR.validate_token(id_token) {
+        // The provider may have rotated its keys since we last fetched them
+        Err(AuthError::UnknownKid(kid)) => match VALIDATOR.key_cache() {
+            Some(keys) if keys.refetch_unknown().await => VALIDATOR.validate_token(id_token),
+            _ => Err(AuthError::UnknownKid(kid)),
+        },
+        claims => claims,
+    }?;
+
+    if !claims.email_verified {
+        info!("Email of user {} is not verified", claims.user_id);
+        return Err(AuthError::EmailNotVerified);
+    }
+
+    Ok(claims)
+}
+
+pub async fn bearer_auth_validator(
+    req: ServiceRequest,
+    credentials: BearerAuth,
+) -> Result<ServiceRequest, Error> {
+    let claims = authenticate(credentials.token())
+        .await
+        .map_err(ApiError::from)?;
+
+    let (http_request, payload) = req.into_parts();
+    http_request.extensions_mut().insert(claims);
+
+    ServiceRequest::from_parts(http_request, payload).map_err(|_| {
+        error!("Could not reconstruct the request after authenticating it");
+        ApiError::from(AuthError::Internal).into()
+    })
+}
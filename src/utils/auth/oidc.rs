@@ -0,0 +1,114 @@
+use color_eyre::{eyre::eyre, eyre::WrapErr, Result};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use super::keys::{self, FetchedKeys, KeyCache, Keys};
+use super::{verify, AuthError, AuthResult, IdentityProvider, UserClaims};
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    issuer: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kid: Option<String>,
+    kty: String,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+/// Standard claims of an OIDC ID token
+#[derive(Debug, Deserialize)]
+struct OidcClaims {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+/// Validates the ID tokens of any OpenID Connect provider, whose signing keys are
+/// found through discovery
+pub struct OidcProvider {
+    validation: Validation,
+    validation_keys: KeyCache,
+}
+
+impl OidcProvider {
+    pub fn new(issuer: &str, audience: &str) -> Result<OidcProvider> {
+        let discovery_url = format!("{}{}", issuer.trim_end_matches('/'), DISCOVERY_PATH);
+        let (body, _) = keys::fetch(&discovery_url)?;
+        let discovery: Discovery = serde_json::from_str(&body)
+            .wrap_err_with(|| format!("Unexpected discovery document at {}", discovery_url))?;
+
+        if discovery.issuer != issuer {
+            return Err(eyre!(
+                "Discovery document is for issuer {}, expected {}",
+                discovery.issuer,
+                issuer
+            ));
+        }
+
+        let mut validation = Validation {
+            iss: Some(discovery.issuer),
+            algorithms: vec![Algorithm::RS256, Algorithm::RS384, Algorithm::RS512],
+            ..Validation::default()
+        };
+        validation.set_audience(&[audience]);
+
+        let jwks_uri = discovery.jwks_uri;
+        let validation_keys = KeyCache::new(move || get_validation_keys(&jwks_uri));
+
+        Ok(OidcProvider {
+            validation,
+            validation_keys,
+        })
+    }
+}
+
+impl IdentityProvider for OidcProvider {
+    fn validate_token(&self, id_token: &str) -> AuthResult<UserClaims> {
+        let claims: OidcClaims = verify(id_token, &self.validation_keys, &self.validation)?;
+
+        let email = claims.email.ok_or(AuthError::MissingEmail)?;
+
+        Ok(UserClaims {
+            user_id: claims.sub,
+            email,
+            email_verified: claims.email_verified,
+        })
+    }
+
+    fn key_cache(&self) -> Option<&KeyCache> {
+        Some(&self.validation_keys)
+    }
+}
+
+fn get_validation_keys(jwks_uri: &str) -> Result<FetchedKeys> {
+    let (body, max_age) = keys::fetch(jwks_uri)?;
+    let jwks: JwkSet = serde_json::from_str(&body)
+        .wrap_err_with(|| format!("Unexpected key set at {}", jwks_uri))?;
+
+    // Only RSA signing keys are supported
+    let keys: Keys = jwks
+        .keys
+        .into_iter()
+        .filter(|jwk| jwk.kty == "RSA")
+        .filter_map(|jwk| match (jwk.kid, jwk.n, jwk.e) {
+            (Some(kid), Some(n), Some(e)) => {
+                Some((kid, DecodingKey::from_rsa_components(&n, &e).into_static()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok(FetchedKeys { keys, max_age })
+}
@@ -1,4 +1,4 @@
-use color_eyre::eyre::WrapErr;
+use color_eyre::eyre::{eyre, WrapErr};
 use color_eyre::Result;
 use dotenv::dotenv;
 use lazy_static::lazy_static;
@@ -11,6 +11,56 @@ pub struct Config {
     pub port: u32,
     pub postgres_url: String,
     pub enable_bunyan: bool,
+    #[serde(default = "default_game_expiry_interval_secs")]
+    pub game_expiry_interval_secs: u64,
+    #[serde(default = "default_stats_refresh_interval_secs")]
+    pub stats_refresh_interval_secs: u64,
+    #[serde(default = "default_auth_provider")]
+    pub auth_provider: AuthProvider,
+    #[serde(default = "default_firebase_project_id")]
+    pub firebase_project_id: String,
+    pub oidc_issuer: Option<String>,
+    pub oidc_audience: Option<String>,
+    pub dev_auth_secret: Option<String>,
+    #[serde(default)]
+    pub production: bool,
+    #[serde(default = "default_media_dir")]
+    pub media_dir: String,
+    #[serde(default = "default_media_url")]
+    pub media_url: String, // Where the files in `media_dir` can be downloaded from
+}
+
+/// Who issues the tokens our users authenticate with
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthProvider {
+    Firebase,
+    Oidc,
+    Dev, // Self-issued tokens, for local development and tests only
+}
+
+fn default_game_expiry_interval_secs() -> u64 {
+    60
+}
+
+fn default_stats_refresh_interval_secs() -> u64 {
+    300
+}
+
+fn default_auth_provider() -> AuthProvider {
+    AuthProvider::Firebase
+}
+
+fn default_firebase_project_id() -> String {
+    "assassin-8c704".to_string()
+}
+
+fn default_media_dir() -> String {
+    "media".to_string()
+}
+
+fn default_media_url() -> String {
+    "/media".to_string()
 }
 
 lazy_static! {
@@ -25,6 +75,12 @@ impl Config {
 
         let mut conf = config::Config::new();
         conf.merge(config::Environment::default())?;
-        conf.try_into().context("Loading environment variables")
+        let conf: Config = conf.try_into().context("Loading environment variables")?;
+
+        if conf.production && conf.auth_provider == AuthProvider::Dev {
+            return Err(eyre!("The dev auth provider can't be used in production"));
+        }
+
+        Ok(conf)
     }
 }
@@ -2,3 +2,7 @@ pub mod auth;
 pub mod config;
 pub mod genstring;
 pub mod logging;
+pub mod pictures;
+pub mod rating;
+pub mod scheduler;
+pub mod storage;
@@ -0,0 +1,114 @@
+use color_eyre::Result;
+use image::imageops::FilterType;
+use image::io::Reader;
+use image::{DynamicImage, ImageFormat, ImageOutputFormat};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::io::Cursor;
+use thiserror::Error;
+
+use crate::utils::storage::STORAGE;
+
+/// Uploads bigger than this are refused before even looking at them
+pub const MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+const PICTURE_SIZE: u32 = 512;
+const THUMBNAIL_SIZE: u32 = 128;
+const JPEG_QUALITY: u8 = 85;
+
+// Bounds on the dimensions of the uploaded image, checked before decoding it
+const MIN_SIDE: u32 = 64;
+const MAX_SIDE: u32 = 8192;
+
+#[derive(Debug, Error)]
+#[error("The upload is not a valid picture: {0}")]
+pub struct InvalidPicture(pub &'static str);
+
+/// A profile picture, squared and resized to the sizes we serve
+pub struct Picture {
+    full: Vec<u8>,
+    thumbnail: Vec<u8>,
+}
+
+/// URLs under which a picture was stored
+#[derive(Debug, Clone)]
+pub struct StoredPicture {
+    pub url: String,
+    pub thumbnail_url: String,
+}
+
+impl Picture {
+    /// Checks that the upload is a PNG or JPEG image of sensible dimensions, and resizes it
+    pub fn from_upload(upload: &[u8]) -> std::result::Result<Picture, InvalidPicture> {
+        let format = Reader::new(Cursor::new(upload))
+            .with_guessed_format()
+            .map_err(|_| InvalidPicture("unreadable"))?
+            .format();
+
+        if !matches!(format, Some(ImageFormat::Png) | Some(ImageFormat::Jpeg)) {
+            return Err(InvalidPicture("only PNG and JPEG are supported"));
+        }
+
+        let (width, height) = Reader::with_format(Cursor::new(upload), format.unwrap())
+            .into_dimensions()
+            .map_err(|_| InvalidPicture("unreadable"))?;
+
+        if width.min(height) < MIN_SIDE || width.max(height) > MAX_SIDE {
+            return Err(InvalidPicture("dimensions out of bounds"));
+        }
+
+        let image = image::load_from_memory_with_format(upload, format.unwrap())
+            .map_err(|_| InvalidPicture("corrupted image"))?;
+
+        Ok(Picture {
+            full: encode(&image, PICTURE_SIZE)?,
+            thumbnail: encode(&image, THUMBNAIL_SIZE)?,
+        })
+    }
+
+    /// Stores the picture under fresh names, so that clients never get a stale one from a cache
+    pub fn store(&self, player_id: i32) -> Result<StoredPicture> {
+        let suffix: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(12)
+            .map(char::from)
+            .collect();
+        let name = format!("{}-{}", player_id, suffix);
+
+        let url = STORAGE.put(&format!("{}.jpg", name), &self.full)?;
+        let thumbnail_url = STORAGE.put(&format!("{}-thumb.jpg", name), &self.thumbnail)?;
+
+        Ok(StoredPicture { url, thumbnail_url })
+    }
+}
+
+/// URL of the thumbnail of the picture behind the given URL
+pub fn thumbnail_url(url: &str) -> Option<String> {
+    url.strip_suffix(".jpg")
+        .map(|base| format!("{}-thumb.jpg", base))
+}
+
+/// Deletes the files of a picture. Pictures which were not stored by us are left alone.
+pub fn remove(url: &str) -> Result<()> {
+    let thumbnail = thumbnail_url(url);
+
+    for url in std::iter::once(url).chain(thumbnail.as_deref()) {
+        if let Some(name) = STORAGE.name_of(url) {
+            STORAGE.delete(&name)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn encode(image: &DynamicImage, size: u32) -> std::result::Result<Vec<u8>, InvalidPicture> {
+    let resized = image.resize_to_fill(size, size, FilterType::Lanczos3);
+
+    // JPEG has no alpha channel
+    let rgb = DynamicImage::ImageRgb8(resized.to_rgb8());
+
+    let mut encoded = Vec::new();
+    rgb.write_to(&mut encoded, ImageOutputFormat::Jpeg(JPEG_QUALITY))
+        .map_err(|_| InvalidPicture("could not be encoded"))?;
+
+    Ok(encoded)
+}
@@ -0,0 +1,147 @@
+use crate::utils::rating::{AgentResult, RatingSystem};
+
+/// Elo ratings for free-for-all games. Every agent is considered to have won a duel against
+/// each agent who finished below them. Killing a higher-rated agent is worth one more duel
+/// won against them, while kills on weaker agents are already accounted for by the standings.
+#[derive(Debug, Clone)]
+pub struct Elo {
+    /// Most rating points a game can be worth, whatever the number of agents
+    pub k_factor: f64,
+    /// Most rating points a single kill can be worth
+    pub kill_k_factor: f64,
+}
+
+impl Elo {
+    /// Score an agent is expected to get in a duel against the other one
+    fn expected_score(own: i32, other: i32) -> f64 {
+        1.0 / (1.0 + 10f64.powf(f64::from(other - own) / 400.0))
+    }
+}
+
+impl RatingSystem for Elo {
+    fn rate(&self, results: &[AgentResult]) -> Vec<i32> {
+        let mut deltas = vec![0.0; results.len()];
+
+        if results.len() > 1 {
+            // A game against many opponents shouldn't weigh more than a duel
+            let k = self.k_factor / (results.len() - 1) as f64;
+
+            for (i, own) in results.iter().enumerate() {
+                for (j, other) in results.iter().enumerate().filter(|(j, _)| *j != i) {
+                    let actual = if i < j { 1.0 } else { 0.0 };
+                    deltas[i] += k * (actual - Self::expected_score(own.rating, other.rating));
+                }
+            }
+        }
+
+        for (i, killer) in results.iter().enumerate() {
+            for &victim in killer.victims.iter() {
+                let victim_rating = results[victim].rating;
+                if victim_rating > killer.rating {
+                    let gain = self.kill_k_factor
+                        * (1.0 - Self::expected_score(killer.rating, victim_rating));
+                    deltas[i] += gain;
+                    deltas[victim] -= gain;
+                }
+            }
+        }
+
+        results
+            .iter()
+            .zip(deltas)
+            .map(|(result, delta)| result.rating + delta.round() as i32)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rating::results;
+    use std::collections::HashMap;
+
+    const ELO: Elo = Elo {
+        k_factor: 32.0,
+        kill_k_factor: 8.0,
+    };
+
+    fn agent(rating: i32, victims: &[usize]) -> AgentResult {
+        AgentResult {
+            rating,
+            victims: victims.to_vec(),
+        }
+    }
+
+    #[test]
+    fn lone_agent_keeps_their_rating() {
+        assert_eq!(ELO.rate(&[agent(1234, &[])]), vec![1234]);
+    }
+
+    #[test]
+    fn duel_between_equals() {
+        assert_eq!(
+            ELO.rate(&[agent(1000, &[1]), agent(1000, &[])]),
+            vec![1016, 984]
+        );
+    }
+
+    #[test]
+    fn game_weighs_as_much_as_a_duel() {
+        assert_eq!(
+            ELO.rate(&[agent(1000, &[]), agent(1000, &[]), agent(1000, &[])]),
+            vec![1016, 1000, 984]
+        );
+    }
+
+    #[test]
+    fn killing_a_stronger_agent_counts() {
+        assert_eq!(
+            ELO.rate(&[agent(1000, &[]), agent(1200, &[])]),
+            vec![1024, 1176]
+        );
+        assert_eq!(
+            ELO.rate(&[agent(1000, &[1]), agent(1200, &[])]),
+            vec![1030, 1170]
+        );
+    }
+
+    #[test]
+    fn killing_a_weaker_agent_does_not_count() {
+        assert_eq!(
+            ELO.rate(&[agent(1200, &[1]), agent(1000, &[])]),
+            ELO.rate(&[agent(1200, &[]), agent(1000, &[])])
+        );
+    }
+
+    #[test]
+    fn history_of_games() {
+        let mut ratings: HashMap<i32, i32> = (1..=4).map(|id| (id, 1000)).collect();
+
+        // Final standings and kills of each game, in the order they were played
+        let history: Vec<(Vec<i32>, Vec<(i32, i32)>)> = vec![
+            (vec![1, 4, 2, 3], vec![(1, 2), (1, 3)]),
+            (vec![3, 2, 1], vec![(3, 1)]),
+            (vec![2, 3, 4, 1], vec![(2, 4), (3, 1), (2, 3)]),
+        ];
+
+        let mut after_each_game = Vec::new();
+
+        for (players, kills) in history.iter() {
+            let before: Vec<i32> = players.iter().map(|id| ratings[id]).collect();
+            let after = ELO.rate(&results(players, &before, kills));
+            for (id, rating) in players.iter().zip(after) {
+                ratings.insert(*id, rating);
+            }
+            after_each_game.push((1..=4).map(|id| ratings[&id]).collect::<Vec<_>>());
+        }
+
+        assert_eq!(
+            after_each_game,
+            vec![
+                vec![1016, 995, 984, 1005],
+                vec![994, 995, 1005, 1005],
+                vec![978, 1020, 1006, 995],
+            ]
+        );
+    }
+}
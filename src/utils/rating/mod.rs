@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+mod elo;
+
+pub use elo::Elo;
+
+/// The rating system used for the players. Swapping it only affects the games which finish
+/// afterwards: ratings are never recomputed from the history.
+pub static RATING_SYSTEM: &dyn RatingSystem = &Elo {
+    k_factor: 32.0,
+    kill_k_factor: 8.0,
+};
+
+/// How an agent fared in a finished game
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentResult {
+    /// Rating of the agent when the game finished
+    pub rating: i32,
+    /// Positions in the standings of the agents they killed
+    pub victims: Vec<usize>,
+}
+
+/// Something able to turn the outcome of a game into new ratings for its players
+pub trait RatingSystem: Send + Sync {
+    /// Computes the new rating of each agent. The results must be sorted by final standing,
+    /// best agent first, and the new ratings are returned in the same order.
+    fn rate(&self, results: &[AgentResult]) -> Vec<i32>;
+}
+
+/// Puts together the results of a game from its final standings (best player first), the
+/// ratings of the players in the same order, and its kills as `(assassin, target)` pairs
+pub fn results(players: &[i32], ratings: &[i32], kills: &[(i32, i32)]) -> Vec<AgentResult> {
+    let positions: HashMap<i32, usize> = players
+        .iter()
+        .enumerate()
+        .map(|(position, id)| (*id, position))
+        .collect();
+
+    players
+        .iter()
+        .zip(ratings)
+        .map(|(id, rating)| AgentResult {
+            rating: *rating,
+            victims: kills
+                .iter()
+                .filter(|(assassin, _)| assassin == id)
+                .filter_map(|(_, target)| positions.get(target).copied())
+                .collect(),
+        })
+        .collect()
+}
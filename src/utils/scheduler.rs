@@ -0,0 +1,80 @@
+use actix_web::{rt, web};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::models::events;
+use crate::models::game::Game;
+use crate::models::stats::CareerStats;
+use crate::utils::auth;
+use crate::utils::config::CFG;
+
+/// How long to wait before listening to the database again after losing the connection
+const EVENT_RELAY_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Periodically finishes the games whose end time has passed.
+/// Must be called from within the actix runtime.
+pub fn spawn_game_expiry() {
+    let period = Duration::from_secs(CFG.game_expiry_interval_secs);
+
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(period);
+        loop {
+            interval.tick().await;
+            match web::block(Game::expire_games).await {
+                Ok(0) => (),
+                Ok(finished) => info!("Expired {} games", finished),
+                Err(e) => error!("Could not expire games: {:?}", e),
+            }
+        }
+    });
+}
+
+/// Periodically recomputes the career statistics of the players.
+/// Must be called from within the actix runtime.
+pub fn spawn_stats_refresh() {
+    let period = Duration::from_secs(CFG.stats_refresh_interval_secs);
+
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = web::block(CareerStats::refresh).await {
+                error!("Could not refresh player statistics: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Refreshes the signing keys of the identity provider whenever they expire.
+/// Must be called from within the actix runtime.
+pub fn spawn_key_refresh() {
+    let keys = match auth::VALIDATOR.key_cache() {
+        Some(keys) => keys,
+        None => return,
+    };
+
+    rt::spawn(async move {
+        loop {
+            rt::time::delay_for(keys.next_refresh_in()).await;
+            // An unknown key may have triggered a refresh in the meantime
+            if keys.next_refresh_in() == Duration::from_secs(0) {
+                keys.refresh().await;
+            }
+        }
+    });
+}
+
+/// Delivers the game events logged by every server replica to the players connected to
+/// this one. Listening to the database blocks, so it gets a thread of its own.
+pub fn spawn_event_relay() {
+    thread::spawn(|| {
+        let mut last_relayed = None;
+        loop {
+            if let Err(e) = events::relay(&CFG.postgres_url, &mut last_relayed) {
+                error!("Lost the connection relaying game events: {:?}", e);
+            }
+            thread::sleep(EVENT_RELAY_RETRY_DELAY);
+        }
+    });
+}
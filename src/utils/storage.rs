@@ -0,0 +1,90 @@
+use color_eyre::{eyre::WrapErr, Result};
+use lazy_static::lazy_static;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::utils::config::CFG;
+
+lazy_static! {
+    pub static ref STORAGE: Box<dyn Storage> =
+        Box::new(LocalStorage::new(&CFG.media_dir, &CFG.media_url));
+}
+
+/// Somewhere to keep the files uploaded by the users, such as their pictures
+pub trait Storage: Send + Sync {
+    /// Stores the file under the given name, replacing any previous one. Returns its public URL.
+    fn put(&self, name: &str, content: &[u8]) -> Result<String>;
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Removing a file which doesn't exist is not an error
+    fn delete(&self, name: &str) -> Result<()>;
+
+    /// Name of the file behind the given URL, if it was stored here
+    fn name_of(&self, url: &str) -> Option<String>;
+}
+
+/// Keeps the files in a directory of the local filesystem. They are served by `routes::media`.
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: &str, base_url: &str) -> LocalStorage {
+        LocalStorage {
+            root: PathBuf::from(root),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn path_of(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put(&self, name: &str, content: &[u8]) -> Result<String> {
+        fs::create_dir_all(&self.root)
+            .wrap_err_with(|| format!("Could not create directory {:?}", self.root))?;
+
+        fs::write(self.path_of(name), content)
+            .wrap_err_with(|| format!("Could not store file {}", name))?;
+
+        Ok(format!("{}/{}", self.base_url, name))
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path_of(name)) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).wrap_err_with(|| format!("Could not read file {}", name)),
+        }
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        match fs::remove_file(self.path_of(name)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(e).wrap_err_with(|| format!("Could not delete file {}", name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn name_of(&self, url: &str) -> Option<String> {
+        url.strip_prefix(&self.base_url)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|name| is_valid_name(name))
+            .map(|name| name.to_string())
+    }
+}
+
+/// Names are flat and made of safe characters only, so that they can't escape the storage
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}